@@ -68,7 +68,7 @@ async fn main() -> Result<(), error::Error> {
     let (ids, _) = smartcar::get_vehicles(&access, None, None).await?;
 
     // Using the access token, start sending requests to the first vehicle in the list
-    let vehicle = Vehicle::new(&ids.vehicles[0], &access.access_token);
+    let vehicle = Vehicle::new(&ids.vehicles[0], access.access_token.expose());
 
     let (attributes, _) = vehicle.attributes().await?;
     let (odometer, _) = vehicle.odometer().await?;
@@ -129,7 +129,7 @@ async fn _print_instructions(auth_url: &str) {
     println!("{message}");
 
     println!("\nPaste the following URL in a browser to proceed with the Smartcar Connect flow:");
-    let a = auth_url.clone().green();
+    let a = auth_url.green();
     println!("\n{a}");
 
     message = "After you finish, paste your code below:"
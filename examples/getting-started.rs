@@ -127,7 +127,7 @@ async fn get_attributes_handler(
         vehicle_ids
     );
 
-    let vehicle = Vehicle::new(&vehicle_ids.vehicles[0], &access.access_token);
+    let vehicle = Vehicle::new(&vehicle_ids.vehicles[0], access.access_token.expose());
     println!(
         "\nStep 6: Use any id from the Vehicles (plural) instance to make a single Vehicle struct:\n{:#?}",
         vehicle
@@ -0,0 +1,283 @@
+//! Audit records for every remote command sent to a vehicle
+//!
+//! Attach an `AuditTrail` to a `Vehicle` to record its commands
+//! (lock, unlock, start/stop charge, set charge limit, and non-GET requests
+//! sent with `Vehicle::request`) to an `AuditSink`.
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::vehicle::Command;
+
+/// What happened to a command
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The command was sent and Smartcar responded with a success status
+    Success,
+
+    /// The command was sent, but the request failed
+    Failed { error: String },
+
+    /// The command was denied by the vehicle's command guard, or by the permission preflight
+    /// because its permission was not granted, and was not sent
+    Denied { reason: String },
+
+    /// The command was not sent because the command guard is in dry-run mode
+    DryRun,
+}
+
+/// A single command sent (or attempted) to a vehicle
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuditRecord {
+    pub vehicle_id: String,
+    pub command: Command,
+    pub method: String,
+    pub path: String,
+    pub body: Option<Value>,
+    pub timestamp: DateTime<Utc>,
+    pub outcome: AuditOutcome,
+
+    /// The `SC-Request-Id` of the response, if Smartcar responded
+    pub request_id: Option<String>,
+
+    /// Who (or what) sent the command, as supplied by the caller
+    pub actor: String,
+}
+
+/// A destination for audit records
+///
+/// A sink that fails to record a command never changes the result of the command method,
+/// since the command may already have been sent. The failure is passed to the error
+/// handler of the `AuditTrail` instead.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord) -> Result<(), Error>;
+}
+
+/// An audit sink that appends each record to a file as a single line of JSON
+#[derive(Debug)]
+pub struct NdjsonFileSink {
+    file: Mutex<File>,
+}
+
+impl NdjsonFileSink {
+    /// Open (or create) the file at `path` for appending
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<NdjsonFileSink, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(NdjsonFileSink {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl AuditSink for NdjsonFileSink {
+    fn record(&self, record: &AuditRecord) -> Result<(), Error> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        // A poisoned lock only means another thread panicked mid-write
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+type AuditErrorHandler = Arc<dyn Fn(&AuditRecord, &Error) + Send + Sync>;
+
+/// An audit sink paired with the actor that is sending the commands
+///
+/// If the sink fails, the command's own result is still returned, and the failure is
+/// passed to the handler set with `AuditTrail::on_audit_error`.
+#[derive(Clone)]
+pub struct AuditTrail {
+    sink: Arc<dyn AuditSink>,
+    actor: String,
+    on_error: Option<AuditErrorHandler>,
+}
+
+impl fmt::Debug for AuditTrail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditTrail")
+            .field("actor", &self.actor)
+            .field("on_error", &self.on_error.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuditTrail {
+    pub fn new(sink: Arc<dyn AuditSink>, actor: &str) -> AuditTrail {
+        AuditTrail {
+            sink,
+            actor: actor.to_owned(),
+            on_error: None,
+        }
+    }
+
+    /// Handle the records that the sink failed to record, e.g. to alert or to retry them.
+    ///
+    /// Without a handler, failures are only reported as a `tracing` event when the
+    /// `tracing` feature is enabled.
+    pub fn on_audit_error<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&AuditRecord, &Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// Record a command, passing any failure of the sink to the error handler
+    pub(crate) fn record(&self, record: AuditRecord) {
+        if let Err(e) = self.sink.record(&record) {
+            match &self.on_error {
+                Some(handler) => handler(&record, &e),
+                None => log_audit_error(&record, &e),
+            }
+        }
+    }
+}
+
+/// The default audit error handler: a `tracing` event with the `tracing` feature, otherwise nothing
+#[cfg(feature = "tracing")]
+fn log_audit_error(record: &AuditRecord, error: &Error) {
+    tracing::warn!(
+        command = record.command.as_str(),
+        vehicle_id = %record.vehicle_id,
+        error = %error,
+        "smartcar audit sink failed to record a command"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn log_audit_error(_record: &AuditRecord, _error: &Error) {}
+
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemorySink {
+    pub(crate) records: Mutex<Vec<AuditRecord>>,
+}
+
+#[cfg(test)]
+impl AuditSink for MemorySink {
+    fn record(&self, record: &AuditRecord) -> Result<(), Error> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_audit_trail_records_commands() {
+    use crate::policy::{CommandGuard, CommandRule, CommandRules, PolicyDecision};
+    use crate::vehicle::Vehicle;
+
+    let sink = Arc::new(MemorySink::default());
+    let rules = CommandRules::new(PolicyDecision::Allow).add_rule(
+        CommandRule::new(PolicyDecision::Deny("not today".to_string()))
+            .set_command(Command::StopCharge),
+    );
+    let guard = CommandGuard::new(rules)
+        .set_dry_run(true)
+        .set_dry_run_logger(|_| {});
+    let v = Vehicle::new("vehicle-1", "access-token")
+        .set_command_guard(Arc::new(guard))
+        .set_audit_trail(AuditTrail::new(sink.clone(), "nightly-job"));
+
+    v.set_charge_limit(0.8).await.unwrap();
+    assert!(v.stop_charge().await.is_err());
+
+    let records = sink.records.lock().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].command, Command::SetChargeLimit);
+    assert_eq!(records[0].outcome, AuditOutcome::DryRun);
+    assert_eq!(
+        records[0].body,
+        Some(serde_json::json!({ "limit": 0.8_f32 }))
+    );
+    assert_eq!(records[0].actor, "nightly-job");
+    assert_eq!(
+        records[1].outcome,
+        AuditOutcome::Denied {
+            reason: "not today".to_string()
+        }
+    );
+}
+
+#[cfg(test)]
+struct FailingSink;
+
+#[cfg(test)]
+impl AuditSink for FailingSink {
+    fn record(&self, _record: &AuditRecord) -> Result<(), Error> {
+        Err(Error::MissingParameters(String::from("disk full")))
+    }
+}
+
+#[tokio::test]
+async fn test_audit_sink_failure_keeps_command_result() {
+    use crate::policy::{CommandGuard, PolicyDecision};
+    use crate::vehicle::Vehicle;
+
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let failures_ref = failures.clone();
+    let trail = AuditTrail::new(Arc::new(FailingSink), "nightly-job")
+        .on_audit_error(move |record, _| failures_ref.lock().unwrap().push(record.command));
+    let guard = CommandGuard::new(|_: &crate::policy::CommandContext| PolicyDecision::Allow)
+        .set_dry_run(true)
+        .set_dry_run_logger(|_| {});
+    let v = Vehicle::new("vehicle-1", "access-token")
+        .set_command_guard(Arc::new(guard))
+        .set_audit_trail(trail);
+
+    assert!(v.unlock().await.is_ok());
+    assert_eq!(*failures.lock().unwrap(), vec![Command::Unlock]);
+}
+
+#[test]
+fn test_ndjson_file_sink() {
+    let path = std::env::temp_dir().join(format!("smartcar-audit-{}.ndjson", std::process::id()));
+    let sink = NdjsonFileSink::open(&path).unwrap();
+    let record = AuditRecord {
+        vehicle_id: "vehicle-1".to_string(),
+        command: Command::Lock,
+        method: "POST".to_string(),
+        path: "/security".to_string(),
+        body: Some(serde_json::json!({ "action": "LOCK" })),
+        timestamp: Utc::now(),
+        outcome: AuditOutcome::Success,
+        request_id: Some("request-id".to_string()),
+        actor: "tester".to_string(),
+    };
+    sink.record(&record).unwrap();
+    sink.record(&record).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let parsed: AuditRecord = serde_json::from_str(lines[1]).unwrap();
+    assert_eq!(parsed.command, Command::Lock);
+    assert_eq!(parsed.request_id, Some("request-id".to_string()));
+}
@@ -1,8 +1,15 @@
 //! Everything needed for Smartcar Connect and getting tokens
 
-use crate::helpers::{format_flag_query, get_connect_url, get_oauth_url};
-use crate::request::{get_basic_b64_auth_header, HttpVerb, MultiQuery, SmartcarRequestBuilder};
+use crate::compatibility::Country;
+use crate::endpoints::Endpoints;
+use crate::flags::Flags;
+use crate::helpers::env_fallbacks;
+use crate::make::Make;
+use crate::request::{
+    encode_query_value, get_basic_b64_auth_header, HttpVerb, MultiQuery, SmartcarRequestBuilder,
+};
 use crate::response::{Access, Meta};
+use crate::secret::Secret;
 use crate::ScopeBuilder;
 use crate::{error, request};
 
@@ -11,13 +18,16 @@ use std::{collections::HashMap, env};
 /// Pass in options to build a Smartcar Connect URL.
 ///
 /// [Info about Smartcar Connect](https://smartcar.com/docs/api/#smartcar-connect)
+#[derive(Debug, Clone)]
 pub struct AuthUrlOptionsBuilder {
     pub force_prompt: Option<bool>,
     pub state: Option<String>,
-    pub make_bypass: Option<String>,
+    pub make_bypass: Option<Make>,
     pub single_select: Option<bool>,
     pub single_select_by_vin: Option<String>,
-    pub flags: Option<HashMap<String, String>>,
+    pub flags: Option<Flags>,
+    pub user: Option<String>,
+    pub country: Option<Country>,
 }
 
 impl Default for AuthUrlOptionsBuilder {
@@ -35,6 +45,8 @@ impl AuthUrlOptionsBuilder {
             single_select_by_vin: None,
             single_select: None,
             flags: None,
+            user: None,
+            country: None,
         }
     }
 
@@ -64,7 +76,7 @@ impl AuthUrlOptionsBuilder {
     ///
     /// [Info about Smartcar Connect ](https://smartcar.com/docs/api/#smartcar-connect)
     /// [Info about Brand Select](https://smartcar.com/docs/api/#brand-select)
-    pub fn set_make_bypass(mut self, make: String) -> Self {
+    pub fn set_make_bypass(mut self, make: Make) -> Self {
         self.make_bypass = Some(make);
         self
     }
@@ -82,6 +94,8 @@ impl AuthUrlOptionsBuilder {
 
     /// Only allow user to select a single vehicle with a specific vin.
     ///
+    /// The vin is not validated, use `smartcar::vin::validate` to catch typos first.
+    ///
     /// Valid names can be found [here](https://smartcar.com/docs/api/#makes)
     ///
     /// [Info about Smartcar Connect ](https://smartcar.com/docs/api/#smartcar-connect)
@@ -95,10 +109,51 @@ impl AuthUrlOptionsBuilder {
     ///
     /// [Info about Smartcar Connect ](https://smartcar.com/docs/api/#smartcar-connect)
     /// [Info about Flags](https://smartcar.com/docs/api/#flags)
-    pub fn set_flags(mut self, flags: &HashMap<String, String>) -> Self {
-        self.flags = Some(flags.to_owned());
+    pub fn set_flags(mut self, flags: Flags) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// Set the Smartcar user id of a user who already connected a vehicle,
+    /// so newly connected vehicles are added to the same user.
+    ///
+    /// [Info about Smartcar Connect ](https://smartcar.com/docs/api/#smartcar-connect)
+    pub fn set_user(mut self, user: String) -> Self {
+        self.user = Some(user);
+        self
+    }
+
+    /// Preselect the user's country in Smartcar Connect, sent as the `country` flag
+    ///
+    /// [Info about Country Selection](https://smartcar.com/docs/connect/global-availability/country-selection)
+    pub fn set_country(mut self, country: Country) -> Self {
+        self.country = Some(country);
         self
     }
+
+    /// Check for options that conflict with each other.
+    ///
+    /// `AuthClient::try_get_auth_url` calls this for you. Use it when the options
+    /// come from user input or configuration.
+    pub fn validate(&self) -> Result<(), error::Error> {
+        if self.single_select == Some(false) && self.single_select_by_vin.is_some() {
+            return Err(error::Error::InvalidConnectOptions(String::from(
+                "single_select_by_vin requires single_select to be enabled",
+            )));
+        }
+
+        let flag_country = self.flags.as_ref().and_then(|f| f.get("country"));
+        if let (Some(country), Some(flag)) = (&self.country, flag_country) {
+            if country.as_str() != flag {
+                return Err(error::Error::InvalidConnectOptions(format!(
+                    "country {} conflicts with the country flag {}",
+                    country, flag
+                )));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MultiQuery for AuthUrlOptionsBuilder {
@@ -116,24 +171,34 @@ impl MultiQuery for AuthUrlOptionsBuilder {
         }
 
         if let Some(make) = &self.make_bypass {
-            query_string.push(("make".to_string(), make.to_owned()));
+            query_string.push(("make".to_string(), make.to_string()));
         }
 
-        if let Some(flags) = &self.flags {
-            let flag_query = format_flag_query(flags);
-            query_string.push(("flag".to_string(), flag_query));
+        if let Some(user) = &self.user {
+            query_string.push(("user".to_string(), user.to_owned()));
+        }
+
+        let mut flags = self.flags.to_owned().unwrap_or_default();
+        if let Some(country) = &self.country {
+            flags = flags.set_country(country);
+        }
+        if !flags.is_empty() {
+            query_string.push(("flags".to_string(), flags.query_value()));
         }
 
-        match &self.single_select_by_vin {
-            Some(vin) => {
+        // Single select can only be turned off explicitly, which also drops the VIN
+        match (&self.single_select_by_vin, self.single_select) {
+            (_, Some(false)) => {
+                query_string.push(("single_select".to_string(), "false".to_string()));
+            }
+            (Some(vin), _) => {
                 query_string.push(("single_select_vin".to_string(), vin.to_owned()));
                 query_string.push(("single_select".to_string(), "true".to_string()));
             }
-            None => {
-                if let Some(enabled) = &self.single_select {
-                    query_string.push(("single_select".to_string(), enabled.to_string()));
-                }
+            (None, Some(true)) => {
+                query_string.push(("single_select".to_string(), "true".to_string()));
             }
+            (None, None) => {}
         }
         query_string
     }
@@ -142,17 +207,17 @@ impl MultiQuery for AuthUrlOptionsBuilder {
 #[test]
 fn get_auth_url_options_query_build() {
     let options = AuthUrlOptionsBuilder::new()
-        .set_make_bypass("mercedes".to_string())
+        .set_make_bypass(Make::MercedesBenz)
         .set_state("no-michael-no-no-michael".to_string())
         .set_single_select_by_vin("THATISSONOTRIGHT".to_string())
         .set_force_prompt(true);
 
     let query = options.vectorize();
 
-    let expecting = vec![
+    let expecting = [
         ("approval_prompt".to_string(), "force".to_string()),
         ("state".to_string(), "no-michael-no-no-michael".to_string()),
-        ("make".to_string(), "mercedes".to_string()),
+        ("make".to_string(), "MERCEDES_BENZ".to_string()),
         (
             "single_select_vin".to_string(),
             "THATISSONOTRIGHT".to_string(),
@@ -164,19 +229,146 @@ fn get_auth_url_options_query_build() {
     assert!(query.iter().all(|q| expecting.contains(q)));
 }
 
+#[test]
+fn get_auth_url_options_user_country_and_validation() {
+    let options = AuthUrlOptionsBuilder::new()
+        .set_user("user-1".to_string())
+        .set_flags(Flags::new().add_flag("tesla_auth", "true"))
+        .set_country(Country::GB);
+
+    let query = options.vectorize();
+    assert!(query.contains(&("user".to_string(), "user-1".to_string())));
+    assert!(query.contains(&(
+        "flags".to_string(),
+        "tesla_auth:true country:GB".to_string()
+    )));
+    assert!(options.validate().is_ok());
+
+    let conflicting = AuthUrlOptionsBuilder::new()
+        .set_single_select(false)
+        .set_single_select_by_vin("5YJ3E1EA2KF317000".to_string());
+    assert!(conflicting.validate().is_err());
+    assert_eq!(
+        conflicting.vectorize(),
+        vec![("single_select".to_string(), "false".to_string())]
+    );
+
+    let conflicting_country = AuthUrlOptionsBuilder::new()
+        .set_flags(Flags::new().set_country(&Country::US))
+        .set_country(Country::CA);
+    assert!(conflicting_country.validate().is_err());
+}
+
+/// The parts of a Smartcar Connect URL, e.g. one generated by `AuthClient::get_auth_url`
+///
+/// The `client_secret` of the URL (if any, e.g. from older versions of this crate) is ignored.
+#[derive(Debug)]
+pub struct ConnectUrl {
+    /// The origin (and any path prefix) of Smartcar Connect, e.g. `https://connect.smartcar.com`
+    pub connect_origin: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub test_mode: bool,
+    pub scope: ScopeBuilder,
+    pub options: AuthUrlOptionsBuilder,
+}
+
+impl ConnectUrl {
+    /// Parse a Smartcar Connect authorize URL, e.g. from a support ticket
+    pub fn parse(connect_url: &str) -> Result<ConnectUrl, error::Error> {
+        let invalid = |reason: &str| error::Error::InvalidConnectUrl(reason.to_owned());
+        let url = url::Url::parse(connect_url).map_err(|e| invalid(&e.to_string()))?;
+        if !url.path().ends_with("/oauth/authorize") {
+            return Err(invalid("the path is not /oauth/authorize"));
+        }
+
+        let mut client_id = None;
+        let mut redirect_uri = None;
+        let mut test_mode = false;
+        let mut scope = ScopeBuilder::new();
+        let mut options = AuthUrlOptionsBuilder::new();
+
+        for (key, value) in url.query_pairs() {
+            let value = value.into_owned();
+            match key.as_ref() {
+                "client_id" => client_id = Some(value),
+                "redirect_uri" => redirect_uri = Some(value),
+                "mode" => test_mode = value == "test",
+                "scope" => {
+                    scope = value
+                        .parse()
+                        .unwrap_or_else(|never| match never {})
+                }
+                "approval_prompt" if value == "force" => options = options.set_force_prompt(true),
+                "state" => options = options.set_state(value),
+                "make" => {
+                    let make = value
+                        .parse()
+                        .unwrap_or_else(|never| match never {});
+                    options = options.set_make_bypass(make);
+                }
+                "single_select" => options = options.set_single_select(value == "true"),
+                "single_select_vin" => options = options.set_single_select_by_vin(value),
+                "user" => options = options.set_user(value),
+                "flags" => {
+                    let flags = value
+                        .split(' ')
+                        .filter(|flag| !flag.is_empty())
+                        .fold(Flags::new(), |flags, flag| match flag.split_once(':') {
+                            Some((name, value)) => flags.add_flag(name, value),
+                            None => flags.add_flag(flag, ""),
+                        });
+                    options = options.set_flags(flags);
+                }
+                _ => {}
+            }
+        }
+
+        let connect_origin = format!(
+            "{}{}",
+            url.origin().ascii_serialization(),
+            url.path().trim_end_matches("/oauth/authorize")
+        );
+
+        Ok(ConnectUrl {
+            connect_origin,
+            client_id: client_id.ok_or_else(|| invalid("missing client_id"))?,
+            redirect_uri: redirect_uri.ok_or_else(|| invalid("missing redirect_uri"))?,
+            test_mode,
+            scope,
+            options,
+        })
+    }
+
+    /// An `AuthClient` that generates this URL again with `AuthClient::get_auth_url`
+    pub fn auth_client(&self, client_secret: &str) -> AuthClient {
+        let endpoints = Endpoints::default().set_connect(&self.connect_origin);
+        AuthClient::new(
+            &self.client_id,
+            client_secret,
+            &self.redirect_uri,
+            self.test_mode,
+        )
+        .set_endpoints(endpoints)
+    }
+}
+
 /// Smartcar OAuth client for your application
 ///
 /// Vist the [Smartcar Developer Portal](https://developer.smartcar.com)
 /// to get these fields.
 ///
 /// Login/Signup for a Smartcar account here [here](https://smartcar.com/subscribe)
+///
+/// Create one with `AuthClient::new` (or `AuthClient::from_env`), since more fields may be added.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct AuthClient {
     /// The application’s unique identifier, obtained
     pub client_id: String,
 
     /// The application secret identfier. If forgotten, it must be regenerated in the dashboard.
-    pub client_secret: String,
+    pub client_secret: Secret,
 
     /// The URI a user will be redirected to after authorization.
     /// This value must match one of the redirect URIs set in the
@@ -185,27 +377,58 @@ pub struct AuthClient {
 
     /// Launch the Smartcar auth flow in test mode
     pub test_mode: bool,
+
+    /// Default: `Endpoints::default()`
+    pub endpoints: Endpoints,
 }
 
 impl AuthClient {
     /// Create an AuthClient instance from environment variables.
-    /// This is the preferred way to create an AuthClient Instance.
+    ///
+    /// Panics if any of them is not set, use `AuthClient::try_from_env` to handle that instead.
     pub fn from_env(test_mode: bool) -> AuthClient {
-        let client_id = env::var("SMARTCAR_CLIENT_ID")
-            .expect("SMARTCAR_CLIENT_ID environment variable not set");
-
-        let client_secret = env::var("SMARTCAR_CLIENT_SECRET")
-            .expect("SMARTCAR_CLIENT_SECRET envionment variable not set");
+        match AuthClient::try_from_env(test_mode) {
+            Ok(client) => client,
+            Err(e) => panic!("{:?}", e),
+        }
+    }
 
-        let redirect_uri = env::var("SMARTCAR_REDIRECT_URI")
-            .expect("SMARTCAR_REDIRECT_URI envionment variable not set");
+    /// Create an AuthClient instance from the `SMARTCAR_CLIENT_ID`, `SMARTCAR_CLIENT_SECRET`,
+    /// and `SMARTCAR_REDIRECT_URI` environment variables.
+    /// This is the preferred way to create an AuthClient Instance.
+    ///
+    /// Returns `Error::MissingParameters` listing every variable that is not set.
+    pub fn try_from_env(test_mode: bool) -> Result<AuthClient, error::Error> {
+        AuthClient::try_from_vars(test_mode, |name| env::var(name).ok())
+    }
 
-        AuthClient {
+    /// `try_from_env`, reading the variables with `vars`
+    fn try_from_vars(
+        test_mode: bool,
+        vars: impl Fn(&str) -> Option<String>,
+    ) -> Result<AuthClient, error::Error> {
+        let [client_id, client_secret, redirect_uri] = env_fallbacks(
+            [
+                (None, "SMARTCAR_CLIENT_ID"),
+                (None, "SMARTCAR_CLIENT_SECRET"),
+                (None, "SMARTCAR_REDIRECT_URI"),
+            ],
+            vars,
+        )
+        .map_err(|missing| {
+            error::Error::MissingParameters(format!(
+                "auth client::environment variables not set: {}",
+                missing.join(", ")
+            ))
+        })?;
+
+        Ok(AuthClient {
             client_id,
-            client_secret,
+            client_secret: Secret::from(client_secret),
             redirect_uri,
             test_mode,
-        }
+            endpoints: Endpoints::default(),
+        })
     }
 
     pub fn new(
@@ -216,25 +439,61 @@ impl AuthClient {
     ) -> AuthClient {
         AuthClient {
             client_id: client_id.to_string(),
-            client_secret: client_secret.to_string(),
+            client_secret: Secret::from(client_secret),
             redirect_uri: redirect_uri.to_string(),
             test_mode,
+            endpoints: Endpoints::default(),
         }
     }
 
+    /// Send requests (and generate Connect URLs) to these endpoints instead of `Endpoints::default()`
+    pub fn set_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
     /// Generate the Smartcar Connect URL, which will allow your userse to securely
     /// grant your application permissions to interact with their vehicle.
     ///
+    /// The options are expected to be valid (see `AuthUrlOptionsBuilder::validate`); this
+    /// panics on conflicting options in debug builds. Use `try_get_auth_url` for options
+    /// that come from user input or configuration.
+    ///
     /// [Info about Smartcar Connect](https://smartcar.com/docs/api/#smartcar-connect)
     pub fn get_auth_url(
         &self,
         scope: &ScopeBuilder,
         options: Option<&AuthUrlOptionsBuilder>,
     ) -> String {
-        let mut url = get_connect_url();
+        debug_assert!(
+            options.iter().all(|opt| opt.validate().is_ok()),
+            "conflicting Smartcar Connect options, use AuthClient::try_get_auth_url to handle them"
+        );
+        self.build_auth_url(scope, options)
+    }
+
+    /// Like `get_auth_url`, but returns an error if the options conflict with each other
+    /// (see `AuthUrlOptionsBuilder::validate`)
+    pub fn try_get_auth_url(
+        &self,
+        scope: &ScopeBuilder,
+        options: Option<&AuthUrlOptionsBuilder>,
+    ) -> Result<String, error::Error> {
+        if let Some(opt) = options {
+            opt.validate()?;
+        }
+        Ok(self.build_auth_url(scope, options))
+    }
+
+    fn build_auth_url(
+        &self,
+        scope: &ScopeBuilder,
+        options: Option<&AuthUrlOptionsBuilder>,
+    ) -> String {
+        let mut url = self.endpoints.connect.to_owned();
 
         url.push_str("/oauth/authorize?scope=");
-        url.push_str(&scope.query_value);
+        url.push_str(&encode_query_value(&scope.query_value));
         url.push_str("&response_type=code&");
         url.push_str(&self.multi_query());
 
@@ -250,7 +509,7 @@ impl AuthClient {
             url.push_str("&approval_prompt=auto");
         };
 
-        url.replace(' ', "%20")
+        url
     }
 
     /// Exhange your oauth code for an access token
@@ -263,10 +522,10 @@ impl AuthClient {
             ("redirect_uri", &self.redirect_uri),
         ]);
 
-        let (res, meta) = SmartcarRequestBuilder::new(&get_oauth_url(), HttpVerb::Post)
+        let (res, meta) = SmartcarRequestBuilder::new(&self.endpoints.auth, HttpVerb::Post)
             .add_header(
                 "Authorization",
-                &request::get_basic_b64_auth_header(&self.client_id, &self.client_secret),
+                &request::get_basic_b64_auth_header(&self.client_id, self.client_secret.expose()),
             )
             .add_header("content_type", "application/x-www-form-urlencoded")
             .add_form(form)
@@ -290,10 +549,10 @@ impl AuthClient {
             ("refresh_token", refresh_token),
         ]);
 
-        let (res, meta) = SmartcarRequestBuilder::new(&get_oauth_url(), HttpVerb::Post)
+        let (res, meta) = SmartcarRequestBuilder::new(&self.endpoints.auth, HttpVerb::Post)
             .add_header(
                 "Authorization",
-                &get_basic_b64_auth_header(&self.client_id, &self.client_secret),
+                &get_basic_b64_auth_header(&self.client_id, self.client_secret.expose()),
             )
             .add_header("content_type", "application/x-www-form-urlencoded")
             .add_form(form)
@@ -311,7 +570,6 @@ impl MultiQuery for AuthClient {
         let mut query = Vec::new();
 
         query.push(("client_id".to_string(), self.client_id.to_owned()));
-        query.push(("client_secret".to_string(), self.client_secret.to_owned()));
         query.push(("redirect_uri".to_string(), self.redirect_uri.to_owned()));
 
         if self.test_mode {
@@ -329,12 +587,129 @@ fn get_auth_url() {
     let options = AuthUrlOptionsBuilder::new();
     let auth_url = ac.get_auth_url(&scope, Some(&options));
 
-    let expecting = String::from("https://connect.smartcar.com/oauth/authorize?scope=control_charge%20control_security%20read_battery%20read_charge%20read_engine_oil%20read_fuel%20read_location%20read_odometer%20read_security%20read_tires%20read_vehicle_info%20read_vin%20read_compass%20read_speedometer%20read_thermometer&response_type=code&client_id=test-client-id&client_secret=test-client-secret&redirect_uri=test.com&mode=test&approval_prompt=auto");
+    let expecting = String::from("https://connect.smartcar.com/oauth/authorize?scope=control_charge%20control_security%20read_battery%20read_charge%20read_engine_oil%20read_fuel%20read_location%20read_odometer%20read_security%20read_tires%20read_vehicle_info%20read_vin&response_type=code&client_id=test-client-id&redirect_uri=test.com&mode=test&approval_prompt=auto");
     assert_eq!(auth_url, expecting);
 }
 
+#[test]
+fn try_get_auth_url_rejects_conflicting_options() {
+    let ac = AuthClient::new("test-client-id", "test-client-secret", "test.com", true);
+    let scope = ScopeBuilder::with_all_permissions();
+
+    let options =
+        AuthUrlOptionsBuilder::new().set_single_select_by_vin("5YJ3E1EA2KF317000".to_string());
+    assert_eq!(
+        ac.try_get_auth_url(&scope, Some(&options))
+            .unwrap(),
+        ac.get_auth_url(&scope, Some(&options))
+    );
+
+    let conflicting = options.set_single_select(false);
+    assert!(matches!(
+        ac.try_get_auth_url(&scope, Some(&conflicting)),
+        Err(error::Error::InvalidConnectOptions(_))
+    ));
+}
+
+#[test]
+fn get_auth_url_with_required_permissions() {
+    use crate::Permission;
+
+    let ac = AuthClient::new("test-client-id", "test-client-secret", "test.com", true);
+    let scope = ScopeBuilder::new()
+        .add_required_permission(Permission::ReadVehicleInfo)
+        .add_permission(Permission::ReadOdometer);
+    let auth_url = ac.get_auth_url(&scope, None);
+
+    assert!(auth_url.starts_with(
+        "https://connect.smartcar.com/oauth/authorize?scope=required:read_vehicle_info%20read_odometer&"
+    ));
+}
+
+#[test]
+fn parse_connect_url() {
+    use crate::Permission;
+
+    let ac = AuthClient::new("client-id", "secret", "https://example.com/callback", true);
+    let scope = ScopeBuilder::new()
+        .add_required_permission(Permission::ReadVin)
+        .add_permission(Permission::ReadBattery);
+    let options = AuthUrlOptionsBuilder::new()
+        .set_state("a=b&c d".to_string())
+        .set_make_bypass(Make::Tesla)
+        .set_country(Country::DE);
+    let url = ac.get_auth_url(&scope, Some(&options));
+
+    let parsed = ConnectUrl::parse(&url).unwrap();
+    assert_eq!(parsed.client_id, "client-id");
+    assert_eq!(parsed.redirect_uri, "https://example.com/callback");
+    assert!(parsed.test_mode);
+    assert!(parsed.scope.is_required(&Permission::ReadVin));
+    assert_eq!(parsed.options.state.as_deref(), Some("a=b&c d"));
+    assert_eq!(parsed.options.make_bypass, Some(Make::Tesla));
+    assert_eq!(
+        parsed
+            .options
+            .flags
+            .as_ref()
+            .and_then(|f| f.get("country")),
+        Some("DE")
+    );
+
+    let regenerated = parsed
+        .auth_client("secret")
+        .get_auth_url(&parsed.scope, Some(&parsed.options));
+    assert_eq!(regenerated, url);
+
+    assert!(
+        ConnectUrl::parse("https://connect.smartcar.com/oauth/authorize?scope=read_vin").is_err()
+    );
+}
+
 #[test]
 #[should_panic]
 fn create_auth_client_without_env_variables() {
     AuthClient::from_env(true);
 }
+
+#[test]
+fn try_create_auth_client_without_env_variables() {
+    match AuthClient::try_from_vars(true, |_| None) {
+        Err(error::Error::MissingParameters(msg)) => assert!(
+            msg.contains("SMARTCAR_CLIENT_ID, SMARTCAR_CLIENT_SECRET, SMARTCAR_REDIRECT_URI")
+        ),
+        other => panic!("expected missing parameters, got {:?}", other),
+    }
+
+    let vars = HashMap::from([
+        ("SMARTCAR_CLIENT_ID", "env-id"),
+        ("SMARTCAR_CLIENT_SECRET", "env-secret"),
+    ]);
+    match AuthClient::try_from_vars(true, |name| vars.get(name).map(|v| v.to_string())) {
+        Err(error::Error::MissingParameters(msg)) => {
+            assert!(msg.ends_with("environment variables not set: SMARTCAR_REDIRECT_URI"))
+        }
+        other => panic!("expected missing parameters, got {:?}", other),
+    }
+}
+
+#[test]
+fn get_auth_url_with_custom_endpoints() {
+    use crate::Permission;
+
+    let ac = AuthClient::new("test-client-id", "test-client-secret", "test.com", true)
+        .set_endpoints(Endpoints::from_origin("http://localhost:8080"));
+    let auth_url = ac.get_auth_url(
+        &ScopeBuilder::new().add_permission(Permission::ReadVin),
+        None,
+    );
+    assert!(auth_url.starts_with("http://localhost:8080/oauth/authorize?scope=read_vin&"));
+    assert_eq!(ac.endpoints.auth, "http://localhost:8080/oauth/token");
+
+    let parsed = ConnectUrl::parse(&auth_url).unwrap();
+    assert_eq!(parsed.connect_origin, "http://localhost:8080");
+    let regenerated = parsed
+        .auth_client("test-client-secret")
+        .get_auth_url(&parsed.scope, Some(&parsed.options));
+    assert_eq!(regenerated, auth_url);
+}
@@ -0,0 +1,351 @@
+//! The countries and regions that Smartcar supports, and helpers for the Compatibility API.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::Error;
+use crate::response::{Capability, Compatibility, CompatibilityMatrixModel};
+#[cfg(test)]
+use crate::Permission;
+use crate::ScopeBuilder;
+
+pub mod bulk;
+
+/// A region of the Compatibility Matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    Us,
+    Ca,
+    Europe,
+}
+
+impl Region {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Region::Us => "US",
+            Region::Ca => "CA",
+            Region::Europe => "EUROPE",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A country where Smartcar is available, by its ISO 3166-1 alpha-2 code
+///
+/// [More info on Countries](https://smartcar.com/docs/connect/global-availability/country-selection)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Country {
+    AT,
+    BE,
+    BG,
+    CA,
+    CH,
+    CY,
+    CZ,
+    DE,
+    DK,
+    EE,
+    ES,
+    FI,
+    FR,
+    GB,
+    GR,
+    HR,
+    HU,
+    IE,
+    IS,
+    IT,
+    LI,
+    LT,
+    LU,
+    LV,
+    MT,
+    NL,
+    NO,
+    PL,
+    PT,
+    RO,
+    SE,
+    SI,
+    SK,
+    US,
+    /// A country that this SDK does not know of yet, by its uppercase code
+    Other(String),
+}
+
+const COUNTRIES: [Country; 34] = [
+    Country::AT,
+    Country::BE,
+    Country::BG,
+    Country::CA,
+    Country::CH,
+    Country::CY,
+    Country::CZ,
+    Country::DE,
+    Country::DK,
+    Country::EE,
+    Country::ES,
+    Country::FI,
+    Country::FR,
+    Country::GB,
+    Country::GR,
+    Country::HR,
+    Country::HU,
+    Country::IE,
+    Country::IS,
+    Country::IT,
+    Country::LI,
+    Country::LT,
+    Country::LU,
+    Country::LV,
+    Country::MT,
+    Country::NL,
+    Country::NO,
+    Country::PL,
+    Country::PT,
+    Country::RO,
+    Country::SE,
+    Country::SI,
+    Country::SK,
+    Country::US,
+];
+
+impl Country {
+    /// All countries that this SDK knows Smartcar is available in
+    pub fn all() -> &'static [Country] {
+        &COUNTRIES
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Country::AT => "AT",
+            Country::BE => "BE",
+            Country::BG => "BG",
+            Country::CA => "CA",
+            Country::CH => "CH",
+            Country::CY => "CY",
+            Country::CZ => "CZ",
+            Country::DE => "DE",
+            Country::DK => "DK",
+            Country::EE => "EE",
+            Country::ES => "ES",
+            Country::FI => "FI",
+            Country::FR => "FR",
+            Country::GB => "GB",
+            Country::GR => "GR",
+            Country::HR => "HR",
+            Country::HU => "HU",
+            Country::IE => "IE",
+            Country::IS => "IS",
+            Country::IT => "IT",
+            Country::LI => "LI",
+            Country::LT => "LT",
+            Country::LU => "LU",
+            Country::LV => "LV",
+            Country::MT => "MT",
+            Country::NL => "NL",
+            Country::NO => "NO",
+            Country::PL => "PL",
+            Country::PT => "PT",
+            Country::RO => "RO",
+            Country::SE => "SE",
+            Country::SI => "SI",
+            Country::SK => "SK",
+            Country::US => "US",
+            Country::Other(code) => code,
+        }
+    }
+
+    /// The region of the Compatibility Matrix that covers this country,
+    /// or `None` for a country this SDK does not know of
+    pub fn region(&self) -> Option<Region> {
+        match self {
+            Country::US => Some(Region::Us),
+            Country::CA => Some(Region::Ca),
+            Country::Other(_) => None,
+            _ => Some(Region::Europe),
+        }
+    }
+}
+
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Country {
+    type Err = Error;
+
+    /// Parse a country code, ignoring case
+    ///
+    /// Two-letter codes that this SDK does not know of are parsed as `Country::Other`.
+    fn from_str(s: &str) -> Result<Country, Error> {
+        let code = s.trim().to_uppercase();
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(Error::UnsupportedCountry(s.to_owned()));
+        }
+
+        let country = COUNTRIES
+            .iter()
+            .find(|c| c.as_str() == code)
+            .cloned()
+            .unwrap_or(Country::Other(code));
+        Ok(country)
+    }
+}
+
+impl Serialize for Country {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Country {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Country, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Build the capabilities of a make, model, and year from its Compatibility Matrix.
+///
+/// `models` is `None` if the matrix has no entry for the make. The matrix cannot be queried
+/// by model or year, so the models that cover them are picked here, ignoring case.
+///
+/// The matrix lists the permissions of each model, not the capability of each endpoint.
+/// Every endpoint of a permission (see `Permission::endpoints`) is thus capable if any
+/// matching model lists that permission, and `reason` is always `None`. A permission that
+/// this SDK does not know of gets a single capability with an empty `endpoint`.
+pub(crate) fn compatibility_from_matrix(
+    models: Option<Vec<CompatibilityMatrixModel>>,
+    model: &str,
+    year: i32,
+    scope: &ScopeBuilder,
+) -> Compatibility {
+    let models: Vec<CompatibilityMatrixModel> = models
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.model.eq_ignore_ascii_case(model))
+        .filter(|m| m.start_year <= year && year <= m.end_year.unwrap_or(i32::MAX))
+        .collect();
+
+    let capabilities = scope
+        .ordered_permissions()
+        .flat_map(|permission| {
+            let capable = models.iter().any(|m| {
+                m.permissions
+                    .iter()
+                    .any(|p| p == permission.as_str())
+            });
+            let endpoints = match permission.endpoints() {
+                [] => &[""][..],
+                endpoints => endpoints,
+            };
+
+            endpoints
+                .iter()
+                .map(|endpoint| Capability {
+                    permission: permission.clone(),
+                    endpoint: endpoint.to_string(),
+                    capable,
+                    reason: None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Compatibility {
+        compatible: !models.is_empty(),
+        reason: None,
+        capabilities,
+    }
+}
+
+#[test]
+fn test_country_parsing_and_region() {
+    assert_eq!("us".parse::<Country>().unwrap(), Country::US);
+    assert_eq!(Country::DE.region(), Some(Region::Europe));
+    assert_eq!(Country::CA.region().unwrap().as_str(), "CA");
+    assert_eq!(
+        "jp".parse::<Country>().unwrap(),
+        Country::Other("JP".to_string())
+    );
+    assert_eq!(Country::Other("JP".to_string()).region(), None);
+    assert!("USA".parse::<Country>().is_err());
+    assert!("1A".parse::<Country>().is_err());
+
+    let json = serde_json::to_string(&Country::GB).unwrap();
+    assert_eq!(json, "\"GB\"");
+    assert_eq!(serde_json::from_str::<Country>(&json).unwrap(), Country::GB);
+}
+
+#[test]
+fn test_compatibility_from_matrix() {
+    let matrix: std::collections::HashMap<String, Vec<CompatibilityMatrixModel>> =
+        serde_json::from_str(
+            r#"{
+                "TESLA": [{
+                    "model": "Model 3",
+                    "startYear": 2017,
+                    "endYear": null,
+                    "type": "BEV",
+                    "endpoints": ["EV battery", "Location"],
+                    "permissions": ["read_battery", "read_location"]
+                }, {
+                    "model": "Model 3",
+                    "startYear": 2020,
+                    "endYear": null,
+                    "type": "BEV",
+                    "endpoints": ["EV battery", "Charge status"],
+                    "permissions": ["read_battery", "read_charge"]
+                }]
+            }"#,
+        )
+        .unwrap();
+
+    let scope = ScopeBuilder::new()
+        .add_permissions([Permission::ReadCharge, Permission::ReadFuel])
+        .add_permission(Permission::Unknown(String::from("read_warp_drive")));
+    let compatibility =
+        compatibility_from_matrix(matrix.get("TESLA").cloned(), "model 3", 2021, &scope);
+    assert!(compatibility.compatible);
+    assert_eq!(compatibility.reason, None);
+    let capabilities: Vec<(&str, &str, bool)> = compatibility
+        .capabilities
+        .iter()
+        .map(|c| (c.permission.as_str(), c.endpoint.as_str(), c.capable))
+        .collect();
+    assert_eq!(
+        capabilities,
+        [
+            ("read_charge", "/charge", true),
+            ("read_charge", "/charge/limit", true),
+            ("read_fuel", "/fuel", false),
+            ("read_warp_drive", "", false),
+        ]
+    );
+    assert!(compatibility
+        .capabilities
+        .iter()
+        .all(|c| c.reason.is_none()));
+    assert_eq!(
+        scope
+            .filter_compatible(&compatibility)
+            .query_value,
+        "read_charge"
+    );
+
+    let too_old = compatibility_from_matrix(matrix.get("TESLA").cloned(), "Model 3", 2016, &scope);
+    assert!(!too_old.compatible);
+    assert!(too_old.capabilities.iter().all(|c| !c.capable));
+
+    let unknown_make = compatibility_from_matrix(None, "DMC-12", 1981, &scope);
+    assert!(!unknown_make.compatible);
+}
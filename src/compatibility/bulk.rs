@@ -0,0 +1,304 @@
+//! Check the compatibility of many VINs at once, e.g. from a spreadsheet.
+
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use tokio::{
+    sync::Mutex,
+    time::{interval, Interval, MissedTickBehavior},
+};
+
+use super::Country;
+use crate::error::Error;
+use crate::response::Compatibility;
+use crate::{get_compatibility, CompatibilityOptions, Permission, ScopeBuilder};
+
+/// Whether a vehicle is capable of every endpoint of a single permission
+#[derive(Debug, Serialize)]
+pub struct PermissionCapability {
+    pub permission: String,
+    pub capable: bool,
+    /// The reason of the first endpoint that the vehicle is not capable of
+    pub reason: Option<String>,
+}
+
+/// The compatibility of a single VIN.
+///
+/// If the check itself failed (e.g. a network error), `error` holds the failure and
+/// `compatible` is `None`.
+#[derive(Debug, Serialize)]
+pub struct VinCompatibility {
+    pub vin: String,
+    pub country: Country,
+    pub compatible: Option<bool>,
+    pub reason: Option<String>,
+    pub permissions: Vec<PermissionCapability>,
+    pub error: Option<String>,
+}
+
+impl VinCompatibility {
+    fn from_result(
+        vin: String,
+        country: Country,
+        permissions: &[Permission],
+        result: Result<Compatibility, Error>,
+    ) -> VinCompatibility {
+        let compatibility = match result {
+            Ok(c) => c,
+            Err(e) => {
+                let error = match e {
+                    Error::SmartcarError(sc) => sc.to_string(),
+                    other => other.to_string(),
+                };
+                return VinCompatibility {
+                    vin,
+                    country,
+                    compatible: None,
+                    reason: None,
+                    permissions: Vec::new(),
+                    error: Some(error),
+                };
+            }
+        };
+
+        let permissions = permissions
+            .iter()
+            .map(|p| {
+                let mut capabilities = compatibility
+                    .capabilities
+                    .iter()
+                    .filter(|c| &c.permission == p)
+                    .peekable();
+                let listed = capabilities.peek().is_some();
+                let not_capable = capabilities.find(|c| !c.capable);
+
+                PermissionCapability {
+                    permission: p.as_str().to_owned(),
+                    capable: listed && not_capable.is_none(),
+                    reason: match not_capable {
+                        Some(c) => c.reason.to_owned(),
+                        None if !listed => compatibility.reason.to_owned(),
+                        None => None,
+                    },
+                }
+            })
+            .collect();
+
+        VinCompatibility {
+            vin,
+            country,
+            compatible: Some(compatibility.compatible),
+            reason: compatibility.reason,
+            permissions,
+            error: None,
+        }
+    }
+}
+
+/// The compatibility of every VIN of a bulk check, in the same order as the VINs
+#[derive(Debug, Serialize)]
+pub struct BulkCompatibilityReport {
+    /// The permissions of the scope that was checked, in the order of the CSV columns
+    pub permissions: Vec<String>,
+    pub results: Vec<VinCompatibility>,
+}
+
+impl BulkCompatibilityReport {
+    /// The VINs whose compatibility could not be checked
+    pub fn failed(&self) -> impl Iterator<Item = &VinCompatibility> {
+        self.results.iter().filter(|r| r.error.is_some())
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self.results)?)
+    }
+
+    /// A CSV table with a row per VIN, and a `capable` and `reason` column per permission
+    pub fn to_csv(&self) -> String {
+        let mut header = vec![
+            String::from("vin"),
+            String::from("country"),
+            String::from("compatible"),
+            String::from("reason"),
+            String::from("error"),
+        ];
+        for p in &self.permissions {
+            header.push(format!("{}_capable", p));
+            header.push(format!("{}_reason", p));
+        }
+
+        let mut csv = csv_row(&header);
+        for r in &self.results {
+            let mut row = vec![
+                r.vin.to_owned(),
+                r.country.to_string(),
+                r.compatible
+                    .map(|c| c.to_string())
+                    .unwrap_or_default(),
+                r.reason.to_owned().unwrap_or_default(),
+                r.error.to_owned().unwrap_or_default(),
+            ];
+            for p in &self.permissions {
+                match r.permissions.iter().find(|c| &c.permission == p) {
+                    Some(c) => {
+                        row.push(c.capable.to_string());
+                        row.push(c.reason.to_owned().unwrap_or_default());
+                    }
+                    None => row.extend([String::new(), String::new()]),
+                }
+            }
+            csv.push_str(&csv_row(&row));
+        }
+
+        csv
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.to_owned()
+            }
+        })
+        .collect();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Checks the compatibility of many VINs, with bounded concurrency and an optional rate limit
+pub struct BulkCompatibilityChecker {
+    scope: ScopeBuilder,
+    options: CompatibilityOptions,
+    concurrency: usize,
+    requests_per_second: Option<u32>,
+}
+
+impl BulkCompatibilityChecker {
+    /// Check the compatibility of each VIN with the permissions of this scope
+    pub fn new(scope: ScopeBuilder) -> BulkCompatibilityChecker {
+        BulkCompatibilityChecker {
+            scope,
+            options: CompatibilityOptions::default(),
+            concurrency: 5,
+            requests_per_second: None,
+        }
+    }
+
+    /// The client credentials and flags used for every check
+    pub fn set_options(mut self, options: CompatibilityOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Send at most this many requests at a time (Default: 5)
+    pub fn set_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Start at most this many requests per second (Default: no limit)
+    pub fn set_rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = Some(requests_per_second.max(1));
+        self
+    }
+
+    /// Check every VIN, continuing past the VINs that fail
+    pub async fn check<I>(&self, vins: I) -> BulkCompatibilityReport
+    where
+        I: IntoIterator<Item = (String, Country)>,
+    {
+        let permissions: Vec<Permission> = self.scope.ordered_permissions().collect();
+        let limiter: Option<Mutex<Interval>> = self.requests_per_second.map(|rps| {
+            let mut ticks = interval(Duration::from_secs_f64(1.0 / rps as f64));
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            Mutex::new(ticks)
+        });
+        let permissions_ref = &permissions;
+        let limiter_ref = &limiter;
+
+        let results = stream::iter(vins)
+            .map(|(vin, country)| async move {
+                if let Some(limiter) = limiter_ref {
+                    limiter.lock().await.tick().await;
+                }
+                let result = get_compatibility(
+                    &vin,
+                    &self.scope,
+                    country.clone(),
+                    Some(self.options.to_owned()),
+                )
+                .await
+                .map(|(data, _)| data);
+
+                VinCompatibility::from_result(vin, country, permissions_ref, result)
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        BulkCompatibilityReport {
+            permissions: permissions
+                .iter()
+                .map(|p| p.as_str().to_owned())
+                .collect(),
+            results,
+        }
+    }
+}
+
+#[test]
+fn test_bulk_report_rows() {
+    use crate::response::Capability;
+
+    let capability = |permission: &str, endpoint: &str, capable: bool| Capability {
+        permission: permission.parse().unwrap(),
+        endpoint: endpoint.to_owned(),
+        capable,
+        reason: (!capable).then(|| String::from("VEHICLE_NOT_CAPABLE")),
+    };
+    let compatibility = Compatibility {
+        compatible: true,
+        reason: None,
+        capabilities: vec![
+            capability("read_battery", "/battery", true),
+            capability("read_battery", "/battery/capacity", false),
+            capability("read_fuel", "/fuel", true),
+        ],
+    };
+    let permissions = [Permission::ReadBattery, Permission::ReadFuel];
+
+    let report = BulkCompatibilityReport {
+        permissions: vec![String::from("read_battery"), String::from("read_fuel")],
+        results: vec![
+            VinCompatibility::from_result(
+                String::from("VIN1"),
+                Country::US,
+                &permissions,
+                Ok(compatibility),
+            ),
+            VinCompatibility::from_result(
+                String::from("VIN2"),
+                Country::CA,
+                &permissions,
+                Err(Error::MissingParameters(String::from("client id"))),
+            ),
+        ],
+    };
+
+    assert_eq!(report.failed().count(), 1);
+    assert_eq!(
+        report.to_csv(),
+        "vin,country,compatible,reason,error,read_battery_capable,read_battery_reason,read_fuel_capable,read_fuel_reason\r\n\
+         VIN1,US,true,,,false,VEHICLE_NOT_CAPABLE,true,\r\n\
+         VIN2,CA,,,smartcar error::function call with missing params,,,,\r\n"
+    );
+    assert!(report
+        .to_json()
+        .unwrap()
+        .contains("\"vin\":\"VIN2\""));
+}
@@ -0,0 +1,363 @@
+//! Load credentials and settings from a TOML file with named profiles,
+//! so that several tools (e.g. for staging and production) can share one file.
+//!
+//! ```toml
+//! default_profile = "staging"
+//!
+//! [profiles.staging]
+//! client_id = "..."
+//! client_secret = "..."
+//! redirect_uri = "https://staging.example.com/callback"
+//! test_mode = true
+//!
+//! [profiles.production]
+//! amt = "..."
+//! unit_system = "imperial"
+//! ```
+//!
+//! Every setting can be overridden with its env variable, see `Config::load`.
+
+use std::{collections::HashMap, env, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::auth_client::AuthClient;
+use crate::endpoints::Endpoints;
+use crate::error::Error;
+use crate::management::ManagementClient;
+use crate::secret::Secret;
+use crate::vehicle::{UnitSystem, Vehicle};
+
+/// The profile used when neither a profile nor a `default_profile` is given
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, ProfileFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    client_id: Option<String>,
+    client_secret: Option<Secret>,
+    redirect_uri: Option<String>,
+    amt: Option<Secret>,
+    api_origin: Option<String>,
+    auth_url: Option<String>,
+    connect_url: Option<String>,
+    management_origin: Option<String>,
+    test_mode: Option<bool>,
+    unit_system: Option<UnitSystem>,
+}
+
+/// The settings of a single profile
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The name of the profile these settings were loaded from
+    pub profile: String,
+
+    /// Env variable: `SMARTCAR_CLIENT_ID`
+    pub client_id: Option<String>,
+
+    /// Env variable: `SMARTCAR_CLIENT_SECRET`
+    pub client_secret: Option<Secret>,
+
+    /// Env variable: `SMARTCAR_REDIRECT_URI`
+    pub redirect_uri: Option<String>,
+
+    /// Application Management Token. Env variable: `SMARTCAR_AMT`
+    pub amt: Option<Secret>,
+
+    /// Origin of Smartcar API. Env variable: `SMARTCAR_API_ORIGIN`
+    pub api_origin: Option<String>,
+
+    /// Full URL of the token endpoint. Env variable: `SMARTCAR_AUTH_ORIGIN`
+    pub auth_url: Option<String>,
+
+    /// Origin of Smartcar Connect. Env variable: `SMARTCAR_CONNECT_URL`
+    pub connect_url: Option<String>,
+
+    /// Origin of the Management API. Env variable: `SMARTCAR_MANAGEMENT_API_ORIGIN`
+    pub management_origin: Option<String>,
+
+    /// Default: false. Env variable: `SMARTCAR_TEST_MODE`
+    pub test_mode: bool,
+
+    /// Default: metric. Env variable: `SMARTCAR_UNIT_SYSTEM`
+    pub unit_system: UnitSystem,
+}
+
+impl Config {
+    /// Read a profile from a TOML file, then apply the env variable overrides and validate it.
+    ///
+    /// If `profile` is `None`, the file's `default_profile` is used, or else the profile named "default".
+    pub fn load(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Config, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            Error::InvalidConfig(format!("could not read {}: {}", path.display(), e))
+        })?;
+
+        let config =
+            Config::from_toml(&contents, profile)?.with_overrides(|name| env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Parse a profile from TOML, without env variable overrides
+    pub fn from_toml(toml: &str, profile: Option<&str>) -> Result<Config, Error> {
+        let mut file: ConfigFile =
+            toml::from_str(toml).map_err(|e| Error::InvalidConfig(e.message().to_owned()))?;
+
+        let name = profile
+            .map(str::to_owned)
+            .or(file.default_profile)
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_owned());
+        let p = file
+            .profiles
+            .remove(&name)
+            .ok_or_else(|| Error::InvalidConfig(format!("no profile named {}", name)))?;
+
+        Ok(Config {
+            profile: name,
+            client_id: p.client_id,
+            client_secret: p.client_secret,
+            redirect_uri: p.redirect_uri,
+            amt: p.amt,
+            api_origin: p.api_origin,
+            auth_url: p.auth_url,
+            connect_url: p.connect_url,
+            management_origin: p.management_origin,
+            test_mode: p.test_mode.unwrap_or(false),
+            unit_system: p.unit_system.unwrap_or(UnitSystem::Metric),
+        })
+    }
+
+    /// Replace every setting whose variable is set
+    fn with_overrides(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Config, Error> {
+        if let Some(v) = var("SMARTCAR_CLIENT_ID") {
+            self.client_id = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_CLIENT_SECRET") {
+            self.client_secret = Some(Secret::from(v));
+        }
+        if let Some(v) = var("SMARTCAR_REDIRECT_URI") {
+            self.redirect_uri = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_AMT") {
+            self.amt = Some(Secret::from(v));
+        }
+        if let Some(v) = var("SMARTCAR_API_ORIGIN") {
+            self.api_origin = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_AUTH_ORIGIN") {
+            self.auth_url = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_CONNECT_URL") {
+            self.connect_url = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_MANAGEMENT_API_ORIGIN") {
+            self.management_origin = Some(v);
+        }
+        if let Some(v) = var("SMARTCAR_TEST_MODE") {
+            self.test_mode = match v.to_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => {
+                    let msg = format!("SMARTCAR_TEST_MODE must be true or false, not {}", v);
+                    return Err(Error::InvalidConfig(msg));
+                }
+            };
+        }
+        if let Some(v) = var("SMARTCAR_UNIT_SYSTEM") {
+            self.unit_system = match v.to_lowercase().as_str() {
+                "metric" => UnitSystem::Metric,
+                "imperial" => UnitSystem::Imperial,
+                _ => {
+                    let msg = format!("SMARTCAR_UNIT_SYSTEM must be metric or imperial, not {}", v);
+                    return Err(Error::InvalidConfig(msg));
+                }
+            };
+        }
+
+        Ok(self)
+    }
+
+    /// Check that the base URLs are http(s) URLs, and that the client credentials
+    /// are either all set or all unset
+    pub fn validate(&self) -> Result<(), Error> {
+        let urls = [
+            ("api_origin", &self.api_origin),
+            ("auth_url", &self.auth_url),
+            ("connect_url", &self.connect_url),
+            ("management_origin", &self.management_origin),
+        ];
+        for (name, value) in urls {
+            if let Some(value) = value {
+                match url::Url::parse(value) {
+                    Ok(u) if u.scheme() == "http" || u.scheme() == "https" => {}
+                    _ => {
+                        let msg = format!("{} must be an http(s) URL, not {}", name, value);
+                        return Err(Error::InvalidConfig(msg));
+                    }
+                }
+            }
+        }
+
+        let missing = self.missing_client_credentials();
+        if !missing.is_empty() && missing.len() < 3 {
+            let msg = format!(
+                "client_id, client_secret, and redirect_uri must be set together (missing {})",
+                missing.join(", ")
+            );
+            return Err(Error::InvalidConfig(msg));
+        }
+
+        Ok(())
+    }
+
+    fn missing_client_credentials(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.client_id.is_none() {
+            missing.push("client_id");
+        }
+        if self.client_secret.is_none() {
+            missing.push("client_secret");
+        }
+        if self.redirect_uri.is_none() {
+            missing.push("redirect_uri");
+        }
+        missing
+    }
+
+    /// `Endpoints::default()`, with the base URLs of this profile
+    ///
+    /// Base URLs that the profile does not set follow the `SMARTCAR_*` env variables
+    /// read by `Endpoints::default()`, and otherwise use the US hosts.
+    pub fn endpoints(&self) -> Endpoints {
+        let mut endpoints = Endpoints::default();
+        if let Some(api) = &self.api_origin {
+            endpoints = endpoints.set_api(api);
+        }
+        if let Some(auth) = &self.auth_url {
+            endpoints = endpoints.set_auth(auth);
+        }
+        if let Some(connect) = &self.connect_url {
+            endpoints = endpoints.set_connect(connect);
+        }
+        if let Some(management) = &self.management_origin {
+            endpoints = endpoints.set_management(management);
+        }
+        endpoints
+    }
+
+    /// An `AuthClient` with the client credentials, test mode, and endpoints of this profile
+    pub fn auth_client(&self) -> Result<AuthClient, Error> {
+        match (&self.client_id, &self.client_secret, &self.redirect_uri) {
+            (Some(id), Some(secret), Some(redirect_uri)) => {
+                Ok(
+                    AuthClient::new(id, secret.expose(), redirect_uri, self.test_mode)
+                        .set_endpoints(self.endpoints()),
+                )
+            }
+            _ => Err(Error::MissingParameters(format!(
+                "config::profile {} is missing {}",
+                self.profile,
+                self.missing_client_credentials().join(", ")
+            ))),
+        }
+    }
+
+    /// A `ManagementClient` with the AMT and endpoints of this profile
+    pub fn management_client(&self) -> Result<ManagementClient, Error> {
+        match &self.amt {
+            Some(amt) => Ok(ManagementClient::new(amt.expose()).set_endpoints(self.endpoints())),
+            None => Err(Error::MissingParameters(format!(
+                "config::profile {} is missing amt",
+                self.profile
+            ))),
+        }
+    }
+
+    /// A `Vehicle` with the unit system and endpoints of this profile
+    pub fn vehicle(&self, vehicle_id: &str, access_token: &str) -> Vehicle {
+        let mut vehicle = Vehicle::new(vehicle_id, access_token).set_endpoints(self.endpoints());
+        vehicle.unit_system = self.unit_system;
+        vehicle
+    }
+}
+
+#[cfg(test)]
+const TEST_CONFIG: &str = r#"
+default_profile = "staging"
+
+[profiles.staging]
+client_id = "staging-id"
+client_secret = "staging-secret"
+redirect_uri = "https://staging.example.com/callback"
+api_origin = "http://localhost:8080"
+test_mode = true
+
+[profiles.production]
+amt = "production-amt"
+unit_system = "imperial"
+"#;
+
+#[test]
+fn test_config_profiles() {
+    let staging = Config::from_toml(TEST_CONFIG, None).unwrap();
+    assert_eq!(staging.profile, "staging");
+    assert!(staging.validate().is_ok());
+    let ac = staging.auth_client().unwrap();
+    assert_eq!(ac.client_secret.expose(), "staging-secret");
+    assert!(ac.test_mode);
+    assert_eq!(ac.endpoints.api, "http://localhost:8080");
+    assert_eq!(ac.endpoints.connect, Endpoints::default().connect);
+    assert!(staging.management_client().is_err());
+    assert!(!format!("{:?}", staging).contains("staging-secret"));
+
+    let production = Config::from_toml(TEST_CONFIG, Some("production")).unwrap();
+    assert_eq!(production.unit_system, UnitSystem::Imperial);
+    assert_eq!(
+        production.vehicle("id", "token").unit_system,
+        UnitSystem::Imperial
+    );
+    assert!(production.management_client().is_ok());
+    assert!(production.auth_client().is_err());
+
+    assert!(Config::from_toml(TEST_CONFIG, Some("qa")).is_err());
+    assert!(Config::from_toml("[profiles.default]\nclient_idd = \"typo\"", None).is_err());
+}
+
+#[test]
+fn test_config_env_overrides_and_validation() {
+    let env = HashMap::from([
+        ("SMARTCAR_CLIENT_ID", "env-id"),
+        ("SMARTCAR_TEST_MODE", "false"),
+        ("SMARTCAR_UNIT_SYSTEM", "IMPERIAL"),
+    ]);
+    let config = Config::from_toml(TEST_CONFIG, None)
+        .unwrap()
+        .with_overrides(|name| env.get(name).map(|v| v.to_string()))
+        .unwrap();
+    assert_eq!(config.client_id.as_deref(), Some("env-id"));
+    assert!(!config.test_mode);
+    assert_eq!(config.unit_system, UnitSystem::Imperial);
+
+    let invalid = Config::from_toml(TEST_CONFIG, None)
+        .unwrap()
+        .with_overrides(|name| (name == "SMARTCAR_TEST_MODE").then(|| String::from("yes")));
+    assert!(invalid.is_err());
+
+    let mut config = Config::from_toml(TEST_CONFIG, None).unwrap();
+    config.api_origin = Some(String::from("localhost"));
+    assert!(config.validate().is_err());
+
+    let mut config = Config::from_toml(TEST_CONFIG, None).unwrap();
+    config.redirect_uri = None;
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,97 @@
+//! Where requests to Smartcar API, Smartcar Connect, and the Management API are sent.
+
+use crate::helpers::{
+    get_api_url, get_connect_url, get_management_url, get_oauth_url, US_API_ORIGIN, US_AUTH_URL,
+    US_CONNECT_ORIGIN, US_MANAGEMENT_ORIGIN,
+};
+
+/// The base URLs used by a client
+///
+/// `Endpoints::default()` uses the US hosts, unless they are overridden with the
+/// `SMARTCAR_API_ORIGIN`, `SMARTCAR_AUTH_ORIGIN`, `SMARTCAR_CONNECT_URL`, and
+/// `SMARTCAR_MANAGEMENT_API_ORIGIN` env variables. For other hosts, e.g. those of a
+/// Smartcar deployment outside the US, use `Endpoints::from_origin` or the setters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// Origin of Smartcar API, e.g. `https://api.smartcar.com`
+    pub api: String,
+
+    /// Full URL of the token endpoint, e.g. `https://auth.smartcar.com/oauth/token`
+    pub auth: String,
+
+    /// Origin of Smartcar Connect, e.g. `https://connect.smartcar.com`
+    pub connect: String,
+
+    /// Origin of the Management API, e.g. `https://management.smartcar.com`
+    pub management: String,
+}
+
+impl Endpoints {
+    /// The US hosts, ignoring the env variables
+    pub fn us() -> Endpoints {
+        Endpoints {
+            api: String::from(US_API_ORIGIN),
+            auth: String::from(US_AUTH_URL),
+            connect: String::from(US_CONNECT_ORIGIN),
+            management: String::from(US_MANAGEMENT_ORIGIN),
+        }
+    }
+
+    /// Send every request to a single origin, e.g. a local stand-in server in tests
+    ///
+    /// The token endpoint is `{origin}/oauth/token`.
+    pub fn from_origin(origin: &str) -> Endpoints {
+        let origin = origin.trim_end_matches('/');
+        Endpoints {
+            api: origin.to_owned(),
+            auth: format!("{}/oauth/token", origin),
+            connect: origin.to_owned(),
+            management: origin.to_owned(),
+        }
+    }
+
+    pub fn set_api(mut self, origin: &str) -> Self {
+        self.api = origin.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// The full URL of the token endpoint
+    pub fn set_auth(mut self, url: &str) -> Self {
+        self.auth = url.to_owned();
+        self
+    }
+
+    pub fn set_connect(mut self, origin: &str) -> Self {
+        self.connect = origin.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn set_management(mut self, origin: &str) -> Self {
+        self.management = origin.trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Endpoints {
+        Endpoints {
+            api: get_api_url(),
+            auth: get_oauth_url(),
+            connect: get_connect_url(),
+            management: get_management_url(),
+        }
+    }
+}
+
+#[test]
+fn test_endpoints_from_origin() {
+    let local = Endpoints::from_origin("http://localhost:8080/");
+    assert_eq!(local.api, "http://localhost:8080");
+    assert_eq!(local.auth, "http://localhost:8080/oauth/token");
+    assert_eq!(local.connect, "http://localhost:8080");
+    assert_eq!(local.management, "http://localhost:8080");
+
+    let custom = Endpoints::us().set_api("http://localhost:9000");
+    assert_eq!(custom.api, "http://localhost:9000");
+    assert_eq!(custom.connect, US_CONNECT_ORIGIN);
+}
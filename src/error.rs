@@ -4,6 +4,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::vehicle::Command;
+use crate::Permission;
+
 // All potential errors of the library
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -13,6 +16,9 @@ pub enum Error {
     #[error("sdk error::serde_json failed to (de)serialization failure")]
     SdkSerdeFailure(#[from] serde_json::Error),
 
+    #[error("sdk error::io failure")]
+    SdkIoFailure(#[from] std::io::Error),
+
     #[error("sdk error::hmac digest error")]
     SdkHmacInvalidLength(#[from] hmac::digest::InvalidLength),
 
@@ -22,8 +28,42 @@ pub enum Error {
     #[error("smartcar error::error response from smartcar api")]
     SmartcarError(Box<SmartcarError>),
 
+    #[error("sdk error::webhook signature does not match the payload")]
+    InvalidWebhookSignature,
+
+    #[error("sdk error::webhook signature is not a hex encoded HMAC-SHA256")]
+    MalformedWebhookSignature,
+
+    #[error("sdk error::the {0} permission was not granted for this vehicle")]
+    MissingPermission(Permission),
+
+    #[error("sdk error::invalid configuration ({0})")]
+    InvalidConfig(String),
+
+    #[error("sdk error::invalid Smartcar Connect options ({0})")]
+    InvalidConnectOptions(String),
+
+    #[error("sdk error::invalid Smartcar Connect url ({0})")]
+    InvalidConnectUrl(String),
+
+    #[error("sdk error::no permission grants access to the {0} endpoint")]
+    UnknownEndpoint(String),
+
+    #[error("sdk error::invalid vin {vin} ({reason})")]
+    InvalidVin { vin: String, reason: String },
+
+    #[error("sdk error::{0} is not a two-letter country code")]
+    UnsupportedCountry(String),
+
     #[error("choose ONE of vehicle_id OR user_id as a filter")]
     DeleteConnectionsFilterValidationError,
+
+    #[error("sdk error::{command} command to vehicle {vehicle_id} denied by policy ({reason})")]
+    CommandDenied {
+        command: Command,
+        vehicle_id: String,
+        reason: String,
+    },
 }
 
 /// A detailed error response from Smartcar API
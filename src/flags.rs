@@ -0,0 +1,88 @@
+//! Flags for the features of Smartcar Connect and Smartcar API that your application has early access to.
+
+use std::collections::HashMap;
+
+use crate::compatibility::Country;
+
+/// Builder of the flags sent with Smartcar Connect and the Compatibility API
+///
+/// Flags keep the order they were added in. Adding a flag again replaces its value.
+///
+/// [Info about Flags](https://smartcar.com/docs/api/#flags)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flags {
+    flags: Vec<(String, String)>,
+}
+
+impl Flags {
+    pub fn new() -> Flags {
+        Flags { flags: Vec::new() }
+    }
+
+    /// Adds a single flag, e.g. `add_flag("tesla_auth", "true")`
+    pub fn add_flag(mut self, name: &str, value: &str) -> Self {
+        match self.flags.iter_mut().find(|(n, _)| n == name) {
+            Some(flag) => flag.1 = value.to_owned(),
+            None => self
+                .flags
+                .push((name.to_owned(), value.to_owned())),
+        }
+        self
+    }
+
+    /// Sets the `country` flag, which preselects the user's country in Smartcar Connect
+    pub fn set_country(self, country: &Country) -> Self {
+        self.add_flag("country", country.as_str())
+    }
+
+    /// The value of a flag, if it was added
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// The flags formatted as a query value, e.g. `"tesla_auth:true country:GB"`
+    ///
+    /// Note: Does not include the `flags=` query key
+    pub(crate) fn query_value(&self) -> String {
+        self.flags
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl From<&HashMap<String, String>> for Flags {
+    fn from(flags: &HashMap<String, String>) -> Flags {
+        flags
+            .iter()
+            .fold(Flags::new(), |f, (name, value)| f.add_flag(name, value))
+    }
+}
+
+#[test]
+fn formatting_flag_query() {
+    let mut map = HashMap::new();
+    map.insert(String::from("black"), String::from("flag"));
+    map.insert(String::from("good"), String::from("band"));
+
+    let flag_query = Flags::from(&map).query_value();
+
+    // Order of a HashMap is not preserved
+    assert!(flag_query.contains("black:flag"));
+    assert!(flag_query.contains(' '));
+    assert!(flag_query.contains("good:band"));
+
+    let flags = Flags::new()
+        .add_flag("tesla_auth", "true")
+        .set_country(&Country::GB)
+        .add_flag("tesla_auth", "false");
+    assert_eq!(flags.query_value(), "tesla_auth:false country:GB");
+}
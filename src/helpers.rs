@@ -1,64 +1,56 @@
-use std::{collections::HashMap, env};
+use std::env;
+
+pub(crate) const US_API_ORIGIN: &str = "https://api.smartcar.com";
+pub(crate) const US_AUTH_URL: &str = "https://auth.smartcar.com/oauth/token";
+pub(crate) const US_CONNECT_ORIGIN: &str = "https://connect.smartcar.com";
+pub(crate) const US_MANAGEMENT_ORIGIN: &str = "https://management.smartcar.com";
 
 pub(crate) fn get_api_url() -> String {
     match env::var("SMARTCAR_API_ORIGIN") {
         Ok(api_url) => api_url,
-        Err(_) => String::from("https://api.smartcar.com"),
+        Err(_) => String::from(US_API_ORIGIN),
     }
 }
 
 pub(crate) fn get_oauth_url() -> String {
     match env::var("SMARTCAR_AUTH_ORIGIN") {
         Ok(oauth_url) => oauth_url,
-        Err(_) => String::from("https://auth.smartcar.com/oauth/token"),
+        Err(_) => String::from(US_AUTH_URL),
     }
 }
 
 pub(crate) fn get_connect_url() -> String {
     match env::var("SMARTCAR_CONNECT_URL") {
         Ok(connect_url) => connect_url,
-        Err(_) => String::from("https://connect.smartcar.com"),
+        Err(_) => String::from(US_CONNECT_ORIGIN),
     }
 }
 
 pub(crate) fn get_management_url() -> String {
     match env::var("SMARTCAR_MANAGEMENT_API_ORIGIN") {
         Ok(management_url) => management_url,
-        Err(_) => String::from("https://management.smartcar.com"),
+        Err(_) => String::from(US_MANAGEMENT_ORIGIN),
     }
 }
 
-/// Get the request query value for flags
+/// Use each given value, falling back to its env variable, or list every variable that is not set
 ///
-/// Note: Does not include the &flag= query key
-pub(crate) fn format_flag_query(flags: &HashMap<String, String>) -> String {
-    let mut query = String::from("");
-    if flags.keys().len() == 0 {
-        return query;
-    };
-
-    flags.keys().for_each(|flag| {
-        let value = flags.get(flag);
-
-        if let Some(v) = value {
-            let flag_formatted = format!("{}:{} ", flag, v);
-            query.push_str(&flag_formatted);
-        };
+/// Variables are read with `vars`, e.g. `|name| env::var(name).ok()`.
+pub(crate) fn env_fallbacks<const N: usize>(
+    values: [(Option<String>, &'static str); N],
+    vars: impl Fn(&str) -> Option<String>,
+) -> Result<[String; N], Vec<&'static str>> {
+    let mut missing = Vec::new();
+    let values = values.map(|(value, name)| {
+        let value = value.or_else(|| vars(name));
+        if value.is_none() {
+            missing.push(name);
+        }
+        value.unwrap_or_default()
     });
 
-    query.trim_end().to_string()
-}
-
-#[test]
-fn formatting_flag_query() {
-    let mut flags = HashMap::new();
-    flags.insert(String::from("black"), String::from("flag"));
-    flags.insert(String::from("good"), String::from("band"));
-
-    let flag_query = format_flag_query(&flags);
-
-    // Order not preserved
-    assert!(flag_query.contains("black:flag"));
-    assert!(flag_query.contains(' '));
-    assert!(flag_query.contains("good:band"));
+    match missing.is_empty() {
+        true => Ok(values),
+        false => Err(missing),
+    }
 }
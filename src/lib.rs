@@ -20,30 +20,61 @@
 //! to the dashboard, please [request access](https://smartcar.com/subscribe).
 pub(crate) mod helpers;
 
-use serde::Deserialize;
+use futures::Stream;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::{HashMap, HashSet},
-    env,
+    convert::Infallible,
+    env, fmt,
+    str::FromStr,
 };
 
-use helpers::{format_flag_query, get_api_url, get_management_url};
+use compatibility::{Country, Region};
+use endpoints::Endpoints;
+use flags::Flags;
+use helpers::env_fallbacks;
 use request::{get_bearer_token_header, HttpVerb, SmartcarRequestBuilder};
-use response::{Access, Compatibility, DeleteConnections, GetConnections, Meta, User, Vehicles};
+use response::{
+    Access, Compatibility, CompatibilityMatrixModel, DeleteConnections, GetConnection,
+    GetConnections, Meta, User, Vehicles,
+};
 
+pub mod audit;
 pub mod auth_client;
+pub mod compatibility;
+pub mod config;
+pub mod endpoints;
 pub mod error;
+pub mod flags;
+pub mod make;
+pub mod management;
+pub mod paging;
+pub mod policy;
 pub mod request;
 pub mod response;
+pub mod secret;
 pub mod vehicle;
+pub mod vin;
 pub mod webhooks;
 
 /// Return the id of the vehicle owner who granted access to your application.
 ///
 /// [More info on User](https://smartcar.com/docs/api-reference/user)
 pub async fn get_user(acc: &Access) -> Result<(User, Meta), error::Error> {
-    let url = format!("{api_url}/v2.0/user", api_url = get_api_url());
+    get_user_at(acc, &Endpoints::default()).await
+}
+
+/// `get_user`, sent to the Smartcar API origin of `endpoints`
+pub async fn get_user_at(
+    acc: &Access,
+    endpoints: &Endpoints,
+) -> Result<(User, Meta), error::Error> {
+    let url = format!("{api_url}/v2.0/user", api_url = endpoints.api);
     let (res, meta) = SmartcarRequestBuilder::new(&url, HttpVerb::Get)
-        .add_header("Authorization", &get_bearer_token_header(&acc.access_token))
+        .add_header(
+            "Authorization",
+            &get_bearer_token_header(acc.access_token.expose()),
+        )
         .send()
         .await?;
     let data = res.json::<User>().await?;
@@ -59,9 +90,21 @@ pub async fn get_vehicles(
     limit: Option<i32>,
     offset: Option<i32>,
 ) -> Result<(Vehicles, Meta), error::Error> {
-    let url = format!("{api_url}/v2.0/vehicles", api_url = get_api_url());
-    let mut req = SmartcarRequestBuilder::new(&url, HttpVerb::Get)
-        .add_header("Authorization", &get_bearer_token_header(&acc.access_token));
+    get_vehicles_at(acc, limit, offset, &Endpoints::default()).await
+}
+
+/// `get_vehicles`, sent to the Smartcar API origin of `endpoints`
+pub async fn get_vehicles_at(
+    acc: &Access,
+    limit: Option<i32>,
+    offset: Option<i32>,
+    endpoints: &Endpoints,
+) -> Result<(Vehicles, Meta), error::Error> {
+    let url = format!("{api_url}/v2.0/vehicles", api_url = endpoints.api);
+    let mut req = SmartcarRequestBuilder::new(&url, HttpVerb::Get).add_header(
+        "Authorization",
+        &get_bearer_token_header(acc.access_token.expose()),
+    );
 
     if let Some(l) = limit {
         req = req.add_query("limit", &l.to_string())
@@ -76,7 +119,39 @@ pub async fn get_vehicles(
     Ok((data, meta))
 }
 
+/// The number of vehicles requested per page by `vehicles_stream`
+const VEHICLES_PAGE_LIMIT: i32 = 50;
+
+/// Stream the ids of all of the user's vehicles, requesting each page as needed
+///
+/// Use `paging::collect_all` to get every id at once.
+///
+/// More info on [get all vehicles request](https://smartcar.com/docs/api-reference/all-vehicles)
+pub fn vehicles_stream(acc: &Access) -> impl Stream<Item = Result<String, error::Error>> + '_ {
+    vehicles_stream_at(acc, &Endpoints::default())
+}
+
+/// `vehicles_stream`, sent to the Smartcar API origin of `endpoints`
+pub fn vehicles_stream_at<'a>(
+    acc: &'a Access,
+    endpoints: &Endpoints,
+) -> impl Stream<Item = Result<String, error::Error>> + 'a {
+    let endpoints = endpoints.to_owned();
+    paging::offset_stream(move |offset| {
+        let endpoints = endpoints.to_owned();
+        async move {
+            let (data, _) =
+                get_vehicles_at(acc, Some(VEHICLES_PAGE_LIMIT), Some(offset), &endpoints).await?;
+            Ok((data.vehicles, data.paging))
+        }
+    })
+}
+
 /// Options for Compatibility API
+///
+/// Build these with `CompatibilityOptions::new()` and its setters, since more options may be added.
+#[derive(Clone, Default)]
+#[non_exhaustive]
 pub struct CompatibilityOptions {
     /// Client ID from your dashboard
     /// Default: Will use SMARTCAR_CLIENT_ID env variable
@@ -84,80 +159,176 @@ pub struct CompatibilityOptions {
 
     /// Client Secret, generated from your sadhboard
     /// Default: Will use SMARTAR_CLIENT_SECRET env variable
-    pub client_secret: Option<String>,
+    pub client_secret: Option<secret::Secret>,
 
     /// Optional flags that your application has early access to
-    pub flags: Option<HashMap<String, String>>,
+    pub flags: Option<Flags>,
+
+    /// Default: `Endpoints::default()`
+    pub endpoints: Option<Endpoints>,
+}
+
+impl CompatibilityOptions {
+    pub fn new() -> CompatibilityOptions {
+        CompatibilityOptions::default()
+    }
+
+    pub fn set_client_id(mut self, client_id: &str) -> Self {
+        self.client_id = Some(client_id.to_owned());
+        self
+    }
+
+    pub fn set_client_secret(mut self, client_secret: &str) -> Self {
+        self.client_secret = Some(secret::Secret::from(client_secret));
+        self
+    }
+
+    pub fn set_flags(mut self, flags: Flags) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    /// Send requests to these endpoints instead of `Endpoints::default()`
+    pub fn set_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = Some(endpoints);
+        self
+    }
+
+    fn api_url(options: &Option<CompatibilityOptions>) -> String {
+        match options
+            .as_ref()
+            .and_then(|o| o.endpoints.as_ref())
+        {
+            Some(endpoints) => endpoints.api.to_owned(),
+            None => Endpoints::default().api,
+        }
+    }
+}
+
+/// Add the flags and the client credentials of the options (or env variables, read with `vars`)
+/// to a compatibility request
+fn add_compatibility_options(
+    mut req: SmartcarRequestBuilder,
+    options: Option<CompatibilityOptions>,
+    vars: impl Fn(&str) -> Option<String>,
+) -> Result<SmartcarRequestBuilder, error::Error> {
+    let options = options.unwrap_or_default();
+    if let Some(flags) = options.flags {
+        req = req.add_query("flags", &flags.query_value());
+    };
+
+    let [id, secret] = env_fallbacks(
+        [
+            (options.client_id, "SMARTCAR_CLIENT_ID"),
+            (
+                options
+                    .client_secret
+                    .map(|s| s.expose().to_owned()),
+                "SMARTCAR_CLIENT_SECRET",
+            ),
+        ],
+        vars,
+    )
+    .map_err(|missing| {
+        error::Error::MissingParameters(format!(
+            "compatibility::client credentials must be passed via CompatibilityOptions OR as env variables, but {} not set",
+            missing.join(", ")
+        ))
+    })?;
+
+    Ok(req.add_header(
+        "Authorization",
+        &request::get_basic_b64_auth_header(&id, &secret),
+    ))
 }
 
 /// Given a VIN, country, and a list of permissions, determine:
 /// 1. If the car is compatible with smartcar
 /// 2. If the car is capable of the endpoints associated with each permisison
 ///
+/// The vin is not validated, use `vin::validate` to catch typos before sending the request.
+///
 /// [Compatibility API - By Vin](https://smartcar.com/docs/api-reference/compatibility/by-vin)
-/// [Compatibility API - By Region and Make](https://smartcar.com/docs/api-reference/compatibility/by-region-and-make)
 pub async fn get_compatibility(
     vin: &str,
     scope: &ScopeBuilder,
-    country: &str,
+    country: Country,
     options: Option<CompatibilityOptions>,
 ) -> Result<(Compatibility, Meta), error::Error> {
-    let mut client_id = env::var("SMARTCAR_CLIENT_ID");
-    let mut client_secret = env::var("SMARTCAR_CLIENT_SECRET");
-    let url = format!("{}/v2.0/compatibility", get_api_url());
-
-    let mut req = SmartcarRequestBuilder::new(&url, HttpVerb::Get)
+    let url = format!(
+        "{}/v2.0/compatibility",
+        CompatibilityOptions::api_url(&options)
+    );
+    let req = SmartcarRequestBuilder::new(&url, HttpVerb::Get)
         .add_query("vin", vin)
-        .add_query("scope", &scope.query_value)
-        .add_query("country", country);
+        .add_query("scope", &scope.permission_names())
+        .add_query("country", country.as_str());
 
-    if let Some(opts) = options {
-        if let Some(flags) = opts.flags {
-            req = req.add_query("flags", &format_flag_query(&flags));
-        };
-        if let Some(id) = opts.client_id {
-            client_id = Ok(id);
-        };
-        if let Some(secret) = opts.client_secret {
-            client_secret = Ok(secret);
-        };
-    };
+    let (res, meta) = add_compatibility_options(req, options, |name| env::var(name).ok())?
+        .send()
+        .await?;
+    let data = res.json::<Compatibility>().await?;
 
-    let id = match client_id {
-        Err(_) => {
-            let msg = "compatibility::client id must be passed as an env variable (SMARTCAR_CLIENT_ID) OR via CompatibilityOptionsBuilder";
-            return Err(error::Error::MissingParameters(msg.to_string()));
-        }
-        Ok(v) => v,
-    };
-    let secret = match client_secret {
-        Err(_) => {
-            let msg = "compatibility::client secret must be passed as an env variable (SMARTCAR_CLIENT_SECRET) OR via CompatibilityOptionsBuilder";
-            return Err(error::Error::MissingParameters(msg.to_string()));
-        }
-        Ok(v) => v,
-    };
+    Ok((data, meta))
+}
 
-    let (res, meta) = req
-        .add_header(
-            "Authorization",
-            &request::get_basic_b64_auth_header(&id, &secret),
-        )
+/// Given a make, model, year, region, and a list of permissions, determine:
+/// 1. If that model year is compatible with smartcar in the region
+/// 2. If it is capable of the endpoints associated with each permission
+///
+/// This works without a VIN, e.g. to quote coverage before a vehicle is purchased.
+/// The make is matched ignoring case, e.g. `"tesla"` or `"TESLA"`.
+///
+/// The capabilities are built from the Compatibility Matrix, with one `Capability` per
+/// endpoint of each permission in `scope`. The matrix only says which permissions a model
+/// supports, so all endpoints of a permission share its result and no `reason` is given.
+/// The matrix cannot be queried by model or year, so the whole make is fetched and the
+/// models covering `model` and `year` are picked from it.
+///
+/// [Compatibility API - By Region and Make](https://smartcar.com/docs/api-reference/compatibility/by-region-and-make)
+pub async fn get_compatibility_by_make(
+    make: &str,
+    model: &str,
+    year: i32,
+    region: Region,
+    scope: &ScopeBuilder,
+    options: Option<CompatibilityOptions>,
+) -> Result<(Compatibility, Meta), error::Error> {
+    let url = format!(
+        "{}/v2.0/compatibility/matrix",
+        CompatibilityOptions::api_url(&options)
+    );
+    let req = SmartcarRequestBuilder::new(&url, HttpVerb::Get)
+        .add_query("region", region.as_str())
+        .add_query("make", &make.to_uppercase())
+        .add_query("scope", &scope.permission_names());
+
+    let (res, meta) = add_compatibility_options(req, options, |name| env::var(name).ok())?
         .send()
         .await?;
-
-    let data = res.json::<Compatibility>().await?;
+    let matrix = res
+        .json::<HashMap<String, Vec<CompatibilityMatrixModel>>>()
+        .await?;
+    let models = matrix
+        .into_iter()
+        .find(|(m, _)| m.eq_ignore_ascii_case(make))
+        .map(|(_, models)| models);
+    let data = compatibility::compatibility_from_matrix(models, model, year, scope);
 
     Ok((data, meta))
 }
 
 /// Options for get_connections
+#[derive(Debug, Clone, Default)]
 pub struct GetConnectionsFilters {
     pub vehicle_id: Option<String>,
     pub user_id: Option<String>,
 }
 
 /// Paging options for get_connections
+///
+/// `cursor_id` is the `cursor` of the previous page's `PagingCursor`.
+#[derive(Debug, Clone, Default)]
 pub struct GetConnectionsPaging {
     pub cursor_id: Option<String>,
     pub limit: Option<i32>,
@@ -172,10 +343,20 @@ pub async fn get_connections(
     filter: Option<GetConnectionsFilters>,
     paging: Option<GetConnectionsPaging>,
 ) -> Result<(GetConnections, Meta), error::Error> {
-    let url = format!("{}/v2.0/management/connections/", get_management_url());
+    get_connections_at(amt, filter, paging, &Endpoints::default()).await
+}
+
+/// `get_connections`, sent to the Management API origin of `endpoints`
+pub async fn get_connections_at(
+    amt: &str,
+    filter: Option<GetConnectionsFilters>,
+    paging: Option<GetConnectionsPaging>,
+    endpoints: &Endpoints,
+) -> Result<(GetConnections, Meta), error::Error> {
+    let url = format!("{}/v2.0/management/connections/", endpoints.management);
     let mut req = SmartcarRequestBuilder::new(&url, HttpVerb::Get).add_header(
         "Authorization",
-        &request::get_basic_b64_auth_header("default", &amt),
+        &request::get_basic_b64_auth_header("default", amt),
     );
     if let Some(filter) = filter {
         if let Some(vehicle_id) = filter.vehicle_id {
@@ -187,7 +368,7 @@ pub async fn get_connections(
     }
     if let Some(paging) = paging {
         if let Some(cursor_id) = paging.cursor_id {
-            req = req.add_query("cursor_id", cursor_id.as_str())
+            req = req.add_query("cursor", cursor_id.as_str())
         }
         if let Some(limit) = paging.limit {
             req = req.add_query("limit", limit.to_string().as_str())
@@ -199,6 +380,41 @@ pub async fn get_connections(
     Ok((data, meta))
 }
 
+/// Stream all vehicles that are connected to the application associated with the
+/// management API token used, following the paging cursor as each page is consumed.
+///
+/// `limit` is the number of connections requested per page.
+/// Dropping the stream (or using `StreamExt::take_while`, etc.) stops it early,
+/// without requesting the remaining pages.
+///
+/// More info on [get vehicle connections](https://smartcar.com/docs/api-reference/management/get-vehicle-connections)
+pub fn connections_stream(
+    amt: &str,
+    filter: Option<GetConnectionsFilters>,
+    limit: Option<i32>,
+) -> impl Stream<Item = Result<GetConnection, error::Error>> + '_ {
+    connections_stream_at(amt, filter, limit, &Endpoints::default())
+}
+
+/// `connections_stream`, sent to the Management API origin of `endpoints`
+pub fn connections_stream_at<'a>(
+    amt: &'a str,
+    filter: Option<GetConnectionsFilters>,
+    limit: Option<i32>,
+    endpoints: &Endpoints,
+) -> impl Stream<Item = Result<GetConnection, error::Error>> + 'a {
+    let endpoints = endpoints.to_owned();
+    paging::cursor_stream(move |cursor_id| {
+        let filter = filter.to_owned();
+        let endpoints = endpoints.to_owned();
+        async move {
+            let paging = GetConnectionsPaging { cursor_id, limit };
+            let (data, _) = get_connections_at(amt, filter, Some(paging), &endpoints).await?;
+            Ok((data.connections, data.paging.cursor))
+        }
+    })
+}
+
 pub struct DeleteConnectionsFilters {
     pub vehicle_id: Option<String>,
     pub user_id: Option<String>,
@@ -225,15 +441,22 @@ pub async fn delete_connections(
     amt: &str,
     filter: Option<DeleteConnectionsFilters>,
 ) -> Result<(DeleteConnections, Meta), error::Error> {
-    let url = format!("{}/v2.0/management/connections/", get_management_url());
+    delete_connections_at(amt, filter, &Endpoints::default()).await
+}
+
+/// `delete_connections`, sent to the Management API origin of `endpoints`
+pub async fn delete_connections_at(
+    amt: &str,
+    filter: Option<DeleteConnectionsFilters>,
+    endpoints: &Endpoints,
+) -> Result<(DeleteConnections, Meta), error::Error> {
+    let url = format!("{}/v2.0/management/connections/", endpoints.management);
     let mut req = SmartcarRequestBuilder::new(&url, HttpVerb::Delete).add_header(
         "Authorization",
-        &request::get_basic_b64_auth_header("default", &amt),
+        &request::get_basic_b64_auth_header("default", amt),
     );
     if let Some(filter) = filter {
-        if let Err(validation_error) = filter.validate() {
-            return Err(validation_error);
-        }
+        filter.validate()?;
         if let Some(vehicle_id) = filter.vehicle_id {
             req = req.add_query("vehicle_id", vehicle_id.as_str())
         }
@@ -250,7 +473,9 @@ pub async fn delete_connections(
 /// A permission that your application is requesting access to during SmartcarConnect
 ///
 /// [More info about Permissions](https://smartcar.com/docs/api-reference/permissions)
-#[derive(Deserialize, Debug, Eq, PartialEq, Hash, Clone, Copy)]
+///
+/// Displays, parses, and (de)serializes as its name in Smartcar API, e.g. `read_battery`.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
 pub enum Permission {
     // Core Endpoint Permissions:
     ControlCharge,
@@ -275,10 +500,43 @@ pub enum Permission {
     ReadExtendedVehicleInfo,
     ReadSpeedeomter,
     ReadThermometer,
+    /// A permission that this SDK does not know of yet
+    Unknown(String),
 }
 
+/// Every permission that this SDK knows of
+static KNOWN_PERMISSIONS: [Permission; 21] = [
+    Permission::ControlCharge,
+    Permission::ControlClimate,
+    Permission::ControlSecurity,
+    Permission::ReadBattery,
+    Permission::ReadCharge,
+    Permission::ReadChargeEvents,
+    Permission::ReadChargeLocations,
+    Permission::ReadChargeRecords,
+    Permission::ReadClimate,
+    Permission::ReadCompass,
+    Permission::ReadEngineOil,
+    Permission::ReadExtendedVehicleInfo,
+    Permission::ReadFuel,
+    Permission::ReadLocation,
+    Permission::ReadOdometer,
+    Permission::ReadSecurity,
+    Permission::ReadSpeedeomter,
+    Permission::ReadThermometer,
+    Permission::ReadTires,
+    Permission::ReadVehicleInfo,
+    Permission::ReadVin,
+];
+
 impl Permission {
-    fn as_str(&self) -> &str {
+    /// Every permission that this SDK knows of, i.e. all but `Permission::Unknown`
+    pub fn known() -> &'static [Permission] {
+        &KNOWN_PERMISSIONS
+    }
+
+    /// The name of the permission in Smartcar API, e.g. `read_battery`
+    pub fn as_str(&self) -> &str {
         match self {
             Permission::ControlCharge => "control_charge",
             Permission::ControlClimate => "control_climate",
@@ -301,14 +559,109 @@ impl Permission {
             Permission::ReadTires => "read_tires",
             Permission::ReadVehicleInfo => "read_vehicle_info",
             Permission::ReadVin => "read_vin",
+            Permission::Unknown(name) => name,
+        }
+    }
+
+    /// The endpoints that this permission grants access to.
+    ///
+    /// Make-specific endpoints contain a `{make}` placeholder, e.g. `/{make}/compass`.
+    pub fn endpoints(&self) -> &'static [&'static str] {
+        match self {
+            Permission::ControlCharge => &["/charge", "/charge/limit"],
+            Permission::ControlClimate => &["/{make}/climate/cabin"],
+            Permission::ControlSecurity => &["/security"],
+            Permission::ReadBattery => &["/battery", "/battery/capacity"],
+            Permission::ReadCharge => &["/charge", "/charge/limit"],
+            Permission::ReadChargeEvents => &["/{make}/charge/events"],
+            Permission::ReadChargeLocations => &["/{make}/charge/locations"],
+            Permission::ReadChargeRecords => &["/{make}/charge/records"],
+            Permission::ReadClimate => &["/{make}/climate/cabin"],
+            Permission::ReadCompass => &["/{make}/compass"],
+            Permission::ReadEngineOil => &["/engine/oil"],
+            Permission::ReadExtendedVehicleInfo => &["/{make}/attributes"],
+            Permission::ReadFuel => &["/fuel"],
+            Permission::ReadLocation => &["/location"],
+            Permission::ReadOdometer => &["/odometer"],
+            Permission::ReadSecurity => &["/security"],
+            Permission::ReadSpeedeomter => &["/{make}/speedometer"],
+            Permission::ReadThermometer => &["/{make}/thermometer"],
+            Permission::ReadTires => &["/tires/pressure"],
+            Permission::ReadVehicleInfo => &["/"],
+            Permission::ReadVin => &["/vin"],
+            Permission::Unknown(_) => &[],
         }
     }
 }
 
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = Infallible;
+
+    /// Parse the name of a permission, falling back to `Permission::Unknown`
+    fn from_str(s: &str) -> Result<Permission, Infallible> {
+        let permission = match s {
+            "control_charge" => Permission::ControlCharge,
+            "control_climate" => Permission::ControlClimate,
+            "control_security" => Permission::ControlSecurity,
+            "read_battery" => Permission::ReadBattery,
+            "read_charge" => Permission::ReadCharge,
+            "read_charge_events" => Permission::ReadChargeEvents,
+            "read_charge_locations" => Permission::ReadChargeLocations,
+            "read_charge_records" => Permission::ReadChargeRecords,
+            "read_climate" => Permission::ReadClimate,
+            "read_compass" => Permission::ReadCompass,
+            "read_engine_oil" => Permission::ReadEngineOil,
+            "read_extended_vehicle_info" => Permission::ReadExtendedVehicleInfo,
+            "read_fuel" => Permission::ReadFuel,
+            "read_location" => Permission::ReadLocation,
+            "read_odometer" => Permission::ReadOdometer,
+            "read_security" => Permission::ReadSecurity,
+            "read_speedometer" => Permission::ReadSpeedeomter,
+            "read_thermometer" => Permission::ReadThermometer,
+            "read_tires" => Permission::ReadTires,
+            "read_vehicle_info" => Permission::ReadVehicleInfo,
+            "read_vin" => Permission::ReadVin,
+            other => Permission::Unknown(other.to_owned()),
+        };
+
+        Ok(permission)
+    }
+}
+
+impl Serialize for Permission {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Permission {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Permission, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name
+            .parse()
+            .unwrap_or_else(|never| match never {}))
+    }
+}
+
+/// The prefix of a permission that the user cannot deselect in Smartcar Connect
+const REQUIRED_PREFIX: &str = "required:";
+
 /// Builder of a list of permissions
-#[derive(Deserialize, Debug)]
+///
+/// Permissions are optional unless added as required, in which case the user
+/// cannot deselect them in Smartcar Connect.
+#[derive(Deserialize, Debug, Clone)]
 pub struct ScopeBuilder {
     pub permissions: HashSet<Permission>,
+    /// The permissions that the user cannot deselect, a subset of `permissions`
+    #[serde(default)]
+    pub required: HashSet<Permission>,
     query_value: String,
 }
 
@@ -322,52 +675,130 @@ impl ScopeBuilder {
     pub fn new() -> ScopeBuilder {
         ScopeBuilder {
             permissions: HashSet::new(),
+            required: HashSet::new(),
             query_value: String::from(""),
         }
     }
 
     /// Adds a single permission to the scope builder
-    pub fn add_permission(mut self, p: Permission) -> Self {
-        if !self.permissions.contains(&p) {
-            if !self.query_value.is_empty() {
-                self.query_value.push(' ');
-            };
+    pub fn add_permission(self, p: Permission) -> Self {
+        self.push(p, false)
+    }
 
-            self.query_value.push_str(p.as_str());
-            self.permissions.insert(p);
+    /// Adds a `Vec` or slice of `Permissions` to this scope builder
+    pub fn add_permissions<T>(mut self, permissions: T) -> Self
+    where
+        T: AsRef<[Permission]>,
+    {
+        for p in permissions.as_ref() {
+            self = self.push(p.clone(), false);
         }
 
         self
     }
 
-    /// Adds a `Vec` or slice of `Permissions` to this scope builder
-    pub fn add_permissions<T>(mut self, permissions: T) -> Self
+    /// Adds a single permission that the user cannot deselect in Smartcar Connect.
+    ///
+    /// If the permission was already added as optional, it becomes required.
+    pub fn add_required_permission(self, p: Permission) -> Self {
+        self.push(p, true)
+    }
+
+    /// Adds a `Vec` or slice of `Permissions` that the user cannot deselect in Smartcar Connect
+    pub fn add_required_permissions<T>(mut self, permissions: T) -> Self
     where
         T: AsRef<[Permission]>,
     {
-        let permissions_slice = permissions.as_ref();
+        for p in permissions.as_ref() {
+            self = self.push(p.clone(), true);
+        }
 
-        for p in permissions_slice {
-            if !self.permissions.contains(p) {
-                if !self.query_value.is_empty() {
-                    self.query_value.push(' ');
-                }
+        self
+    }
 
-                self.query_value.push_str(p.as_str());
-                self.permissions.insert(*p);
+    fn push(mut self, p: Permission, required: bool) -> Self {
+        if !self.permissions.contains(&p) {
+            if !self.query_value.is_empty() {
+                self.query_value.push(' ');
             }
+            if required {
+                self.query_value.push_str(REQUIRED_PREFIX);
+                self.required.insert(p.clone());
+            }
+
+            self.query_value.push_str(p.as_str());
+            self.permissions.insert(p);
+        } else if required && self.required.insert(p.clone()) {
+            self.query_value = self
+                .query_value
+                .split(' ')
+                .map(|name| match name == p.as_str() {
+                    true => format!("{}{}", REQUIRED_PREFIX, name),
+                    false => name.to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
         }
 
         self
     }
 
-    /// Create a ScopeBuilder with all available permissions, not including the make-specific permissions
+    /// Returns true if the permission is part of this scope, required or not
+    pub fn contains(&self, p: &Permission) -> bool {
+        self.permissions.contains(p)
+    }
+
+    /// Returns true if the permission is part of this scope and required
+    pub fn is_required(&self, p: &Permission) -> bool {
+        self.required.contains(p)
+    }
+
+    /// The permissions of either scope, in the order of this scope, then the other.
+    ///
+    /// A permission is required if it is required in either scope.
+    pub fn union(&self, other: &ScopeBuilder) -> ScopeBuilder {
+        self.ordered_permissions()
+            .chain(other.ordered_permissions())
+            .fold(ScopeBuilder::new(), |scope, p| {
+                let required = self.is_required(&p) || other.is_required(&p);
+                scope.push(p, required)
+            })
+    }
+
+    /// The permissions of this scope that are not in the other, e.g. the permissions
+    /// to request when re-authorizing a vehicle with `Vehicle::granted_permissions`.
+    pub fn difference(&self, other: &ScopeBuilder) -> ScopeBuilder {
+        self.ordered_permissions()
+            .filter(|p| !other.contains(p))
+            .fold(ScopeBuilder::new(), |scope, p| {
+                let required = self.is_required(&p);
+                scope.push(p, required)
+            })
+    }
+
+    /// The permissions of this scope, in the order they were added
+    pub(crate) fn ordered_permissions(&self) -> impl Iterator<Item = Permission> + '_ {
+        self.query_value.split(' ').filter_map(|name| {
+            let name = name.strip_prefix(REQUIRED_PREFIX).unwrap_or(name);
+            self.permissions
+                .iter()
+                .find(|p| p.as_str() == name)
+                .cloned()
+        })
+    }
+
+    /// The names of the permissions, without the `required:` prefixes
+    /// that only Smartcar Connect understands
+    pub(crate) fn permission_names(&self) -> String {
+        self.ordered_permissions()
+            .map(|p| p.as_str().to_owned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Create a ScopeBuilder with all core permissions, not including the make-specific permissions
     pub fn with_all_permissions() -> ScopeBuilder {
-        ScopeBuilder {
-            permissions: HashSet::new(),
-            query_value: String::from(""),
-        }
-        .add_permissions(vec![
+        ScopeBuilder::new().add_permissions([
             Permission::ControlCharge,
             Permission::ControlSecurity,
             Permission::ReadBattery,
@@ -380,13 +811,120 @@ impl ScopeBuilder {
             Permission::ReadTires,
             Permission::ReadVehicleInfo,
             Permission::ReadVin,
-            // Upcoming Breaking Change: These following permissions will be removed
-            // in the next patch, as they are make-specific permissions
-            Permission::ReadCompass,
-            Permission::ReadSpeedeomter,
-            Permission::ReadThermometer,
         ])
     }
+
+    /// The core permissions to read data that any vehicle may support
+    pub fn core_read() -> ScopeBuilder {
+        ScopeBuilder::new().add_permissions([
+            Permission::ReadVehicleInfo,
+            Permission::ReadVin,
+            Permission::ReadOdometer,
+            Permission::ReadLocation,
+            Permission::ReadSecurity,
+            Permission::ReadTires,
+        ])
+    }
+
+    /// The core permissions to send commands that any vehicle may support
+    pub fn core_control() -> ScopeBuilder {
+        ScopeBuilder::new().add_permissions([Permission::ControlSecurity])
+    }
+
+    /// The permissions to read and control the battery and charging of electric vehicles
+    pub fn ev() -> ScopeBuilder {
+        ScopeBuilder::new().add_permissions([
+            Permission::ReadBattery,
+            Permission::ReadCharge,
+            Permission::ControlCharge,
+        ])
+    }
+
+    /// The permissions to read the fuel tank and engine of internal combustion vehicles
+    pub fn ice() -> ScopeBuilder {
+        ScopeBuilder::new().add_permissions([Permission::ReadFuel, Permission::ReadEngineOil])
+    }
+
+    /// The minimal scope to call each endpoint, e.g. `(HttpVerb::Get, "/battery")`
+    /// or `(HttpVerb::Get, "/tesla/compass")`.
+    ///
+    /// GET requests need `read_*` permissions, and other requests need `control_*` permissions.
+    pub fn for_endpoints(endpoints: &[(HttpVerb, &str)]) -> Result<ScopeBuilder, error::Error> {
+        let mut scope = ScopeBuilder::new();
+        for (verb, path) in endpoints {
+            let prefix = match verb {
+                HttpVerb::Get => "read_",
+                _ => "control_",
+            };
+            let permission = Permission::known()
+                .iter()
+                .filter(|p| p.as_str().starts_with(prefix))
+                .find(|p| {
+                    p.endpoints()
+                        .iter()
+                        .any(|e| endpoint_matches(e, path))
+                })
+                .ok_or_else(|| {
+                    error::Error::UnknownEndpoint(format!("{} {}", verb.as_str(), path))
+                })?;
+
+            scope = scope.add_permission(permission.clone());
+        }
+
+        Ok(scope)
+    }
+
+    /// The permissions of this scope that the vehicle is capable of, according to
+    /// `get_compatibility` or `get_compatibility_by_make`, so users are only asked for
+    /// the permissions that their vehicle supports.
+    ///
+    /// A permission is kept if the vehicle is capable of at least one of its endpoints.
+    pub fn filter_compatible(&self, compatibility: &Compatibility) -> ScopeBuilder {
+        self.ordered_permissions()
+            .filter(|p| {
+                compatibility
+                    .capabilities
+                    .iter()
+                    .any(|c| c.capable && &c.permission == p)
+            })
+            .fold(ScopeBuilder::new(), |scope, p| {
+                let required = self.is_required(&p);
+                scope.push(p, required)
+            })
+    }
+}
+
+/// Returns true if the path matches the endpoint, where `{make}` matches any make
+fn endpoint_matches(endpoint: &str, path: &str) -> bool {
+    let path = match path.len() > 1 {
+        true => path.trim_end_matches('/'),
+        false => path,
+    };
+    let endpoint_segments: Vec<&str> = endpoint.split('/').collect();
+    let path_segments: Vec<&str> = path.split('/').collect();
+
+    endpoint_segments.len() == path_segments.len()
+        && endpoint_segments
+            .iter()
+            .zip(path_segments)
+            .all(|(e, p)| *e == p || (*e == "{make}" && !p.is_empty()))
+}
+
+impl FromStr for ScopeBuilder {
+    type Err = Infallible;
+
+    /// Parse a space-separated scope, e.g. `"required:read_battery read_vin"`
+    fn from_str(s: &str) -> Result<ScopeBuilder, Infallible> {
+        let mut scope = ScopeBuilder::new();
+        for name in s.split_whitespace() {
+            scope = match name.strip_prefix(REQUIRED_PREFIX) {
+                Some(required) => scope.add_required_permission(required.parse()?),
+                None => scope.add_permission(name.parse()?),
+            };
+        }
+
+        Ok(scope)
+    }
 }
 
 #[test]
@@ -401,6 +939,96 @@ fn test_getting_scope_url_params_string() {
     assert_eq!(&permissions.query_value, expecting);
 }
 
+#[test]
+fn test_permission_names_round_trip() {
+    let permission: Permission = "read_battery".parse().unwrap();
+    assert_eq!(permission, Permission::ReadBattery);
+    assert_eq!(permission.to_string(), "read_battery");
+
+    let unknown: Permission = "read_warp_drive".parse().unwrap();
+    assert_eq!(unknown, Permission::Unknown("read_warp_drive".to_string()));
+
+    let json = serde_json::to_string(&[Permission::ReadSpeedeomter, unknown]).unwrap();
+    assert_eq!(json, r#"["read_speedometer","read_warp_drive"]"#);
+    let parsed: Vec<Permission> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed[0], Permission::ReadSpeedeomter);
+
+    let scope: ScopeBuilder = " read_vin  read_fuel read_vin".parse().unwrap();
+    assert_eq!(scope.query_value, "read_vin read_fuel");
+}
+
+#[test]
+fn test_required_permissions_and_scope_algebra() {
+    let granted = ScopeBuilder::new().add_permissions([Permission::ReadVin, Permission::ReadFuel]);
+    let wanted = ScopeBuilder::new()
+        .add_permissions([Permission::ReadVin, Permission::ReadBattery])
+        .add_required_permission(Permission::ReadVin)
+        .add_required_permission(Permission::ReadOdometer);
+    assert_eq!(
+        wanted.query_value,
+        "required:read_vin read_battery required:read_odometer"
+    );
+    assert_eq!(
+        wanted.permission_names(),
+        "read_vin read_battery read_odometer"
+    );
+    assert!(wanted.contains(&Permission::ReadBattery));
+    assert!(!wanted.is_required(&Permission::ReadBattery));
+
+    let reauthorize = wanted.difference(&granted);
+    assert_eq!(
+        reauthorize.query_value,
+        "read_battery required:read_odometer"
+    );
+
+    let union = granted.union(&wanted);
+    assert_eq!(
+        union.query_value,
+        "required:read_vin read_fuel read_battery required:read_odometer"
+    );
+
+    let parsed: ScopeBuilder = union.query_value.parse().unwrap();
+    assert_eq!(parsed.query_value, union.query_value);
+    assert_eq!(parsed.required, union.required);
+}
+
+#[test]
+fn test_scope_for_endpoints() {
+    let scope = ScopeBuilder::for_endpoints(&[
+        (HttpVerb::Get, "/battery"),
+        (HttpVerb::Get, "/charge"),
+        (HttpVerb::Post, "/charge/limit"),
+        (HttpVerb::Get, "/tesla/compass"),
+        (HttpVerb::Get, "/"),
+    ])
+    .unwrap();
+    assert_eq!(
+        scope.query_value,
+        "read_battery read_charge control_charge read_compass read_vehicle_info"
+    );
+
+    assert!(ScopeBuilder::for_endpoints(&[(HttpVerb::Get, "/warp-drive")]).is_err());
+}
+
+#[test]
+fn test_scope_filter_compatible() {
+    let compatibility: Compatibility = serde_json::from_value(serde_json::json!({
+        "compatible": true,
+        "reason": null,
+        "capabilities": [
+            { "permission": "read_battery", "endpoint": "/battery", "capable": true, "reason": null },
+            { "permission": "read_fuel", "endpoint": "/fuel", "capable": false, "reason": "VEHICLE_NOT_CAPABLE" },
+        ]
+    }))
+    .unwrap();
+    let scope = ScopeBuilder::ev()
+        .union(&ScopeBuilder::ice())
+        .add_required_permission(Permission::ReadBattery);
+
+    let supported = scope.filter_compatible(&compatibility);
+    assert_eq!(supported.query_value, "required:read_battery");
+}
+
 #[test]
 fn test_delete_connections_filter_both_options() {
     let filter_with_both_options = DeleteConnectionsFilters {
@@ -422,3 +1050,31 @@ fn test_delete_connections_neither_option() {
     let result = filter_with_neither_option.validate();
     assert!(result.is_err());
 }
+
+#[test]
+fn test_compatibility_options_list_every_missing_credential() {
+    let req = SmartcarRequestBuilder::new("https://api.smartcar.com", HttpVerb::Get);
+    match add_compatibility_options(req, None, |_| None) {
+        Err(error::Error::MissingParameters(msg)) => {
+            assert!(msg.contains("SMARTCAR_CLIENT_ID, SMARTCAR_CLIENT_SECRET"))
+        }
+        _ => panic!("expected missing parameters"),
+    }
+
+    let req = SmartcarRequestBuilder::new("https://api.smartcar.com", HttpVerb::Get);
+    let options = CompatibilityOptions::new().set_client_id("client-id");
+    match add_compatibility_options(req, Some(options.clone()), |_| None) {
+        Err(error::Error::MissingParameters(msg)) => {
+            assert!(!msg.contains("SMARTCAR_CLIENT_ID"));
+            assert!(msg.contains("SMARTCAR_CLIENT_SECRET"));
+        }
+        _ => panic!("expected missing parameters"),
+    }
+
+    let req = SmartcarRequestBuilder::new("https://api.smartcar.com", HttpVerb::Get);
+    let vars = |name: &str| match name {
+        "SMARTCAR_CLIENT_SECRET" => Some(String::from("env-secret")),
+        _ => None,
+    };
+    assert!(add_compatibility_options(req, Some(options), vars).is_ok());
+}
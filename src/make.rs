@@ -0,0 +1,210 @@
+//! The vehicle brands that Smartcar supports.
+
+use std::{convert::Infallible, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A vehicle brand, by its name in Smartcar Connect and Smartcar API
+///
+/// [More info on Makes](https://smartcar.com/docs/api/#makes)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Make {
+    Acura,
+    AlfaRomeo,
+    Audi,
+    Bmw,
+    Buick,
+    Cadillac,
+    Chevrolet,
+    Chrysler,
+    Citroen,
+    Cupra,
+    Dacia,
+    Dodge,
+    Ds,
+    Fiat,
+    Ford,
+    Gmc,
+    Honda,
+    Hyundai,
+    Infiniti,
+    Jaguar,
+    Jeep,
+    Kia,
+    LandRover,
+    Lexus,
+    Lincoln,
+    Mazda,
+    MercedesBenz,
+    Mini,
+    Nissan,
+    Opel,
+    Peugeot,
+    Polestar,
+    Porsche,
+    Ram,
+    Renault,
+    Rivian,
+    Skoda,
+    Subaru,
+    Tesla,
+    Toyota,
+    Vauxhall,
+    Volkswagen,
+    Volvo,
+    /// A make that this SDK does not know of yet
+    Other(String),
+}
+
+/// Every make that this SDK knows of
+static KNOWN_MAKES: [Make; 43] = [
+    Make::Acura,
+    Make::AlfaRomeo,
+    Make::Audi,
+    Make::Bmw,
+    Make::Buick,
+    Make::Cadillac,
+    Make::Chevrolet,
+    Make::Chrysler,
+    Make::Citroen,
+    Make::Cupra,
+    Make::Dacia,
+    Make::Dodge,
+    Make::Ds,
+    Make::Fiat,
+    Make::Ford,
+    Make::Gmc,
+    Make::Honda,
+    Make::Hyundai,
+    Make::Infiniti,
+    Make::Jaguar,
+    Make::Jeep,
+    Make::Kia,
+    Make::LandRover,
+    Make::Lexus,
+    Make::Lincoln,
+    Make::Mazda,
+    Make::MercedesBenz,
+    Make::Mini,
+    Make::Nissan,
+    Make::Opel,
+    Make::Peugeot,
+    Make::Polestar,
+    Make::Porsche,
+    Make::Ram,
+    Make::Renault,
+    Make::Rivian,
+    Make::Skoda,
+    Make::Subaru,
+    Make::Tesla,
+    Make::Toyota,
+    Make::Vauxhall,
+    Make::Volkswagen,
+    Make::Volvo,
+];
+
+impl Make {
+    /// Every make that this SDK knows of, i.e. all but `Make::Other`
+    pub fn known() -> &'static [Make] {
+        &KNOWN_MAKES
+    }
+
+    /// The name of the make in Smartcar API, e.g. `MERCEDES_BENZ`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Make::Acura => "ACURA",
+            Make::AlfaRomeo => "ALFA_ROMEO",
+            Make::Audi => "AUDI",
+            Make::Bmw => "BMW",
+            Make::Buick => "BUICK",
+            Make::Cadillac => "CADILLAC",
+            Make::Chevrolet => "CHEVROLET",
+            Make::Chrysler => "CHRYSLER",
+            Make::Citroen => "CITROEN",
+            Make::Cupra => "CUPRA",
+            Make::Dacia => "DACIA",
+            Make::Dodge => "DODGE",
+            Make::Ds => "DS",
+            Make::Fiat => "FIAT",
+            Make::Ford => "FORD",
+            Make::Gmc => "GMC",
+            Make::Honda => "HONDA",
+            Make::Hyundai => "HYUNDAI",
+            Make::Infiniti => "INFINITI",
+            Make::Jaguar => "JAGUAR",
+            Make::Jeep => "JEEP",
+            Make::Kia => "KIA",
+            Make::LandRover => "LAND_ROVER",
+            Make::Lexus => "LEXUS",
+            Make::Lincoln => "LINCOLN",
+            Make::Mazda => "MAZDA",
+            Make::MercedesBenz => "MERCEDES_BENZ",
+            Make::Mini => "MINI",
+            Make::Nissan => "NISSAN",
+            Make::Opel => "OPEL",
+            Make::Peugeot => "PEUGEOT",
+            Make::Polestar => "POLESTAR",
+            Make::Porsche => "PORSCHE",
+            Make::Ram => "RAM",
+            Make::Renault => "RENAULT",
+            Make::Rivian => "RIVIAN",
+            Make::Skoda => "SKODA",
+            Make::Subaru => "SUBARU",
+            Make::Tesla => "TESLA",
+            Make::Toyota => "TOYOTA",
+            Make::Vauxhall => "VAUXHALL",
+            Make::Volkswagen => "VOLKSWAGEN",
+            Make::Volvo => "VOLVO",
+            Make::Other(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Make {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Make {
+    type Err = Infallible;
+
+    /// Parse the name of a make ignoring case, with spaces or dashes in place of
+    /// underscores (e.g. `"Mercedes-Benz"`), falling back to `Make::Other`
+    fn from_str(s: &str) -> Result<Make, Infallible> {
+        let name = s.trim().to_uppercase().replace([' ', '-'], "_");
+        let make = KNOWN_MAKES
+            .iter()
+            .find(|m| m.as_str() == name)
+            .cloned()
+            .unwrap_or_else(|| Make::Other(s.to_owned()));
+
+        Ok(make)
+    }
+}
+
+impl Serialize for Make {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Make {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Make, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name
+            .parse()
+            .unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[test]
+fn test_make_names() {
+    assert_eq!("Mercedes-Benz".parse::<Make>().unwrap(), Make::MercedesBenz);
+    assert_eq!("tesla".parse::<Make>().unwrap(), Make::Tesla);
+    assert_eq!(Make::LandRover.to_string(), "LAND_ROVER");
+    assert_eq!(
+        "DELOREAN".parse::<Make>().unwrap(),
+        Make::Other("DELOREAN".to_string())
+    );
+}
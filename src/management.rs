@@ -0,0 +1,181 @@
+//! A client for operations that authenticate with your
+//! Application Management Token (AMT), found on the Smartcar Dashboard.
+
+use futures::{stream, Stream, StreamExt};
+
+use crate::endpoints::Endpoints;
+use crate::error::Error;
+use crate::request::{get_bearer_token_header, HttpVerb, SmartcarRequestBuilder};
+use crate::response::{DeleteConnections, GetConnection, GetConnections, Meta, Status};
+use crate::secret::Secret;
+use crate::{DeleteConnectionsFilters, GetConnectionsFilters, GetConnectionsPaging};
+
+/// Holds your Application Management Token to send management requests.
+///
+/// The token is redacted when debug printing this struct.
+#[derive(Debug, Clone)]
+pub struct ManagementClient {
+    amt: Secret,
+    endpoints: Endpoints,
+}
+
+/// The vehicle OR user whose connections should be deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionTarget {
+    Vehicle(String),
+    User(String),
+}
+
+impl From<&ConnectionTarget> for DeleteConnectionsFilters {
+    fn from(target: &ConnectionTarget) -> DeleteConnectionsFilters {
+        match target {
+            ConnectionTarget::Vehicle(id) => DeleteConnectionsFilters {
+                vehicle_id: Some(id.to_owned()),
+                user_id: None,
+            },
+            ConnectionTarget::User(id) => DeleteConnectionsFilters {
+                vehicle_id: None,
+                user_id: Some(id.to_owned()),
+            },
+        }
+    }
+}
+
+/// The result of deleting the connections of a single target in a bulk delete
+#[derive(Debug)]
+pub struct BulkDeleteResult {
+    pub target: ConnectionTarget,
+    pub result: Result<(DeleteConnections, Meta), Error>,
+}
+
+/// The results of a bulk delete, in the same order as the targets
+#[derive(Debug)]
+pub struct BulkDeleteReport {
+    pub results: Vec<BulkDeleteResult>,
+}
+
+impl BulkDeleteReport {
+    /// The targets whose connections were deleted
+    pub fn succeeded(&self) -> impl Iterator<Item = &BulkDeleteResult> {
+        self.results.iter().filter(|r| r.result.is_ok())
+    }
+
+    /// The targets whose connections could not be deleted
+    pub fn failed(&self) -> impl Iterator<Item = &BulkDeleteResult> {
+        self.results.iter().filter(|r| r.result.is_err())
+    }
+}
+
+impl ManagementClient {
+    pub fn new(amt: &str) -> ManagementClient {
+        ManagementClient {
+            amt: Secret::from(amt),
+            endpoints: Endpoints::default(),
+        }
+    }
+
+    /// Send requests to these endpoints instead of `Endpoints::default()`
+    pub fn set_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Returns a paged list of all vehicles that are connected to the application,
+    /// sorted in descending order by connection date.
+    ///
+    /// More info on [get vehicle connections](https://smartcar.com/docs/api-reference/management/get-vehicle-connections)
+    pub async fn get_connections(
+        &self,
+        filter: Option<GetConnectionsFilters>,
+        paging: Option<GetConnectionsPaging>,
+    ) -> Result<(GetConnections, Meta), Error> {
+        crate::get_connections_at(self.amt.expose(), filter, paging, &self.endpoints).await
+    }
+
+    /// Stream all vehicles that are connected to the application,
+    /// following the paging cursor as each page is consumed.
+    ///
+    /// See `connections_stream` for more details.
+    pub fn connections_stream(
+        &self,
+        filter: Option<GetConnectionsFilters>,
+        limit: Option<i32>,
+    ) -> impl Stream<Item = Result<GetConnection, Error>> + '_ {
+        crate::connections_stream_at(self.amt.expose(), filter, limit, &self.endpoints)
+    }
+
+    /// Deletes all vehicle connections associated with a Smartcar user ID or a specific vehicle.
+    ///
+    /// More info on [delete vehicle connections](https://smartcar.com/docs/api-reference/management/delete-vehicle-connections)
+    pub async fn delete_connections(
+        &self,
+        filter: Option<DeleteConnectionsFilters>,
+    ) -> Result<(DeleteConnections, Meta), Error> {
+        crate::delete_connections_at(self.amt.expose(), filter, &self.endpoints).await
+    }
+
+    /// Delete the connections of many vehicles and/or users, sending at most
+    /// `concurrency` requests at a time.
+    ///
+    /// Every target is attempted, even if some of them fail.
+    pub async fn bulk_delete_connections(
+        &self,
+        targets: Vec<ConnectionTarget>,
+        concurrency: usize,
+    ) -> BulkDeleteReport {
+        let results = stream::iter(targets)
+            .map(|target| async move {
+                let result = self
+                    .delete_connections(Some(DeleteConnectionsFilters::from(&target)))
+                    .await;
+                BulkDeleteResult { target, result }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        BulkDeleteReport { results }
+    }
+
+    /// Unsubscribe a vehicle from a webhook
+    ///
+    /// [DELETE - Unsubscribe from Webhook](https://smartcar.com/docs/api-reference/webhooks/unsubscribe-webhook)
+    pub async fn unsubscribe(
+        &self,
+        vehicle_id: &str,
+        webhook_id: &str,
+    ) -> Result<(Status, Meta), Error> {
+        let url = format!(
+            "{api_url}/v2.0/vehicles/{id}/webhooks/{webhook_id}",
+            api_url = self.endpoints.api,
+            id = vehicle_id,
+            webhook_id = webhook_id
+        );
+
+        let (res, meta) = SmartcarRequestBuilder::new(&url, HttpVerb::Delete)
+            .add_header("Authorization", &get_bearer_token_header(self.amt.expose()))
+            .send()
+            .await?;
+        let data = res.json::<Status>().await?;
+
+        Ok((data, meta))
+    }
+}
+
+#[test]
+fn test_management_client_redacts_amt() {
+    let client = ManagementClient::new("super-secret-amt");
+    let debug = format!("{:?}", client);
+
+    assert!(!debug.contains("super-secret-amt"));
+    assert!(debug.contains("[REDACTED]"));
+}
+
+#[test]
+fn test_connection_target_into_filter() {
+    let filter = DeleteConnectionsFilters::from(&ConnectionTarget::User("user-1".to_string()));
+
+    assert_eq!(filter.user_id, Some("user-1".to_string()));
+    assert!(filter.vehicle_id.is_none());
+    assert!(filter.validate().is_ok());
+}
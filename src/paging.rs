@@ -0,0 +1,139 @@
+//! Streams that page through Smartcar's paged responses for you
+
+use std::future::Future;
+
+use futures::{stream, Stream, TryStreamExt};
+
+use crate::error::Error;
+use crate::response::Paging;
+
+/// Collect every item of a paged stream (e.g. `vehicles_stream`) into a `Vec`.
+///
+/// Stops at the first error.
+pub async fn collect_all<T, S>(stream: S) -> Result<Vec<T>, Error>
+where
+    S: Stream<Item = Result<T, Error>>,
+{
+    stream.try_collect().await
+}
+
+/// Stream every item of a limit/offset paged endpoint.
+///
+/// `fetch_page` is called with the offset of the next page, until every element
+/// (according to `Paging.count`) has been returned or a page comes back empty.
+pub(crate) fn offset_stream<T, F, Fut>(fetch_page: F) -> impl Stream<Item = Result<T, Error>>
+where
+    F: FnMut(i32) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Paging), Error>>,
+{
+    stream::try_unfold(
+        (Some(0), fetch_page),
+        |(offset, mut fetch_page)| async move {
+            let offset = match offset {
+                Some(o) => o,
+                None => return Ok::<_, Error>(None),
+            };
+            let (items, paging) = fetch_page(offset).await?;
+            let next_offset = offset + items.len() as i32;
+            let next = if items.is_empty() || next_offset >= paging.count {
+                None
+            } else {
+                Some(next_offset)
+            };
+
+            Ok(Some((
+                stream::iter(items.into_iter().map(Ok)),
+                (next, fetch_page),
+            )))
+        },
+    )
+    .try_flatten()
+}
+
+/// Stream every item of a cursor paged endpoint.
+///
+/// `fetch_page` is called with the cursor of the next page (`None` for the first page),
+/// until a page comes back without a cursor or without any items.
+pub(crate) fn cursor_stream<T, F, Fut>(fetch_page: F) -> impl Stream<Item = Result<T, Error>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, Option<String>), Error>>,
+{
+    stream::try_unfold(
+        (Some(None), fetch_page),
+        |(cursor, mut fetch_page)| async move {
+            let cursor = match cursor {
+                Some(c) => c,
+                None => return Ok::<_, Error>(None),
+            };
+            let (items, next_cursor) = fetch_page(cursor).await?;
+            let next = match next_cursor {
+                Some(c) if !items.is_empty() => Some(Some(c)),
+                _ => None,
+            };
+
+            Ok(Some((
+                stream::iter(items.into_iter().map(Ok)),
+                (next, fetch_page),
+            )))
+        },
+    )
+    .try_flatten()
+}
+
+#[tokio::test]
+async fn test_offset_stream_pages_through_everything() {
+    let all: Vec<i32> = (0..7).collect();
+    let mut requested_offsets = Vec::new();
+
+    let items = collect_all(offset_stream(|offset| {
+        requested_offsets.push(offset);
+        let page: Vec<i32> = all
+            .iter()
+            .skip(offset as usize)
+            .take(3)
+            .copied()
+            .collect();
+        let paging = Paging {
+            count: all.len() as i32,
+            offset,
+        };
+        async move { Ok((page, paging)) }
+    }))
+    .await
+    .unwrap();
+
+    assert_eq!(items, all);
+    assert_eq!(requested_offsets, vec![0, 3, 6]);
+}
+
+#[tokio::test]
+async fn test_cursor_stream_follows_cursor_lazily() {
+    use futures::StreamExt;
+
+    let pages = [
+        (vec![1, 2], Some("b".to_string())),
+        (vec![3, 4], Some("c".to_string())),
+        (vec![5], None),
+    ];
+    let mut requested_cursors = Vec::new();
+
+    let first_three: Vec<i32> = cursor_stream(|cursor: Option<String>| {
+        let index = match cursor.as_deref() {
+            None => 0,
+            Some("b") => 1,
+            _ => 2,
+        };
+        requested_cursors.push(cursor);
+        let page = pages[index].to_owned();
+        async move { Ok(page) }
+    })
+    .take(3)
+    .map(|item| item.unwrap())
+    .collect()
+    .await;
+
+    assert_eq!(first_three, vec![1, 2, 3]);
+    // Stopping early does not request the last page
+    assert_eq!(requested_cursors, vec![None, Some("b".to_string())]);
+}
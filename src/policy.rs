@@ -0,0 +1,358 @@
+//! Guards for the commands sent to a vehicle (lock, unlock, start/stop charge, etc.)
+//!
+//! A `CommandGuard` can be attached to a `Vehicle` to allow, deny, or require confirmation
+//! of a command before it is sent, and to run commands in dry-run mode.
+
+use std::fmt;
+
+use chrono::{DateTime, NaiveTime, Utc};
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::request::HttpVerb;
+use crate::vehicle::Command;
+
+/// The decision of a `CommandPolicy` for a single command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// Send the command
+    Allow,
+
+    /// Do not send the command, with a reason
+    Deny(String),
+
+    /// Only send the command if the confirmation handler of the `CommandGuard` confirms it
+    RequireConfirmation,
+}
+
+/// Information about a command that is about to be sent to a vehicle
+#[derive(Debug)]
+pub struct CommandContext<'a> {
+    pub vehicle_id: &'a str,
+    pub command: Command,
+    pub verb: HttpVerb,
+    pub path: &'a str,
+    pub body: Option<&'a Value>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Decides whether or not a command should be sent to a vehicle
+///
+/// This is implemented for `CommandRules` and for any closure
+/// that takes a `&CommandContext` and returns a `PolicyDecision`.
+pub trait CommandPolicy: Send + Sync {
+    fn decide(&self, context: &CommandContext) -> PolicyDecision;
+}
+
+impl<F> CommandPolicy for F
+where
+    F: Fn(&CommandContext) -> PolicyDecision + Send + Sync,
+{
+    fn decide(&self, context: &CommandContext) -> PolicyDecision {
+        self(context)
+    }
+}
+
+/// A daily window of time (in UTC)
+///
+/// The start is inclusive and the end is exclusive.
+/// If the end is before the start, the window wraps around midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> TimeWindow {
+        TimeWindow { start, end }
+    }
+
+    /// Returns true if the time of day is within this window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// A single rule of `CommandRules`
+///
+/// A rule matches a command if every field that is set matches the command.
+/// A rule without any fields set matches every command.
+#[derive(Debug, Clone)]
+pub struct CommandRule {
+    pub command: Option<Command>,
+    pub vehicle_id: Option<String>,
+    pub time_window: Option<TimeWindow>,
+    pub decision: PolicyDecision,
+}
+
+impl CommandRule {
+    pub fn new(decision: PolicyDecision) -> CommandRule {
+        CommandRule {
+            command: None,
+            vehicle_id: None,
+            time_window: None,
+            decision,
+        }
+    }
+
+    /// Only match this type of command
+    pub fn set_command(mut self, command: Command) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    /// Only match commands sent to this vehicle
+    pub fn set_vehicle_id(mut self, vehicle_id: &str) -> Self {
+        self.vehicle_id = Some(vehicle_id.to_owned());
+        self
+    }
+
+    /// Only match commands sent during this daily window of time
+    pub fn set_time_window(mut self, window: TimeWindow) -> Self {
+        self.time_window = Some(window);
+        self
+    }
+
+    fn matches(&self, context: &CommandContext) -> bool {
+        if let Some(command) = self.command {
+            if command != context.command {
+                return false;
+            }
+        }
+        if let Some(vehicle_id) = &self.vehicle_id {
+            if vehicle_id != context.vehicle_id {
+                return false;
+            }
+        }
+        if let Some(window) = &self.time_window {
+            if !window.contains(context.timestamp.time()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A list of rules, where the first matching rule decides the command
+///
+/// If no rule matches, the default decision is used.
+#[derive(Debug, Clone)]
+pub struct CommandRules {
+    pub rules: Vec<CommandRule>,
+    pub default: PolicyDecision,
+}
+
+impl CommandRules {
+    pub fn new(default: PolicyDecision) -> CommandRules {
+        CommandRules {
+            rules: Vec::new(),
+            default,
+        }
+    }
+
+    /// Adds a rule, which takes priority over the rules added after it
+    pub fn add_rule(mut self, rule: CommandRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+impl CommandPolicy for CommandRules {
+    fn decide(&self, context: &CommandContext) -> PolicyDecision {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(context))
+            .map(|rule| rule.decision.to_owned())
+            .unwrap_or_else(|| self.default.to_owned())
+    }
+}
+
+type ConfirmationHandler = Box<dyn Fn(&CommandContext) -> bool + Send + Sync>;
+type DryRunLogger = Box<dyn Fn(&CommandContext) + Send + Sync>;
+
+/// What a `Vehicle` should do with a command after it passes the guard
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum GuardOutcome {
+    Send,
+    DryRun,
+}
+
+/// Runs every command of a `Vehicle` through a `CommandPolicy` before it is sent.
+///
+/// In dry-run mode, commands that the policy allows are logged instead of being sent.
+pub struct CommandGuard {
+    policy: Box<dyn CommandPolicy>,
+    confirmation: Option<ConfirmationHandler>,
+    dry_run: bool,
+    dry_run_logger: DryRunLogger,
+}
+
+impl fmt::Debug for CommandGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandGuard")
+            .field("confirmation", &self.confirmation.is_some())
+            .field("dry_run", &self.dry_run)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CommandGuard {
+    pub fn new<P>(policy: P) -> CommandGuard
+    where
+        P: CommandPolicy + 'static,
+    {
+        CommandGuard {
+            policy: Box::new(policy),
+            confirmation: None,
+            dry_run: false,
+            dry_run_logger: Box::new(log_dry_run),
+        }
+    }
+
+    /// Set the handler that confirms commands for `PolicyDecision::RequireConfirmation`.
+    ///
+    /// Without this handler, commands that require confirmation are denied.
+    pub fn set_confirmation<F>(mut self, confirm: F) -> Self
+    where
+        F: Fn(&CommandContext) -> bool + Send + Sync + 'static,
+    {
+        self.confirmation = Some(Box::new(confirm));
+        self
+    }
+
+    /// Log allowed commands instead of sending them to the vehicle
+    pub fn set_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Replace the default dry-run logger, which only emits a `tracing` event when the
+    /// `tracing` feature is enabled
+    pub fn set_dry_run_logger<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(&CommandContext) + Send + Sync + 'static,
+    {
+        self.dry_run_logger = Box::new(logger);
+        self
+    }
+
+    pub(crate) fn check(&self, context: &CommandContext) -> Result<GuardOutcome, Error> {
+        let denied = |reason: &str| Error::CommandDenied {
+            command: context.command,
+            vehicle_id: context.vehicle_id.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        match self.policy.decide(context) {
+            PolicyDecision::Allow => {}
+            PolicyDecision::Deny(reason) => return Err(denied(&reason)),
+            PolicyDecision::RequireConfirmation => match &self.confirmation {
+                None => return Err(denied("confirmation required")),
+                Some(confirm) => {
+                    if !confirm(context) {
+                        return Err(denied("confirmation declined"));
+                    }
+                }
+            },
+        }
+
+        if self.dry_run {
+            (self.dry_run_logger)(context);
+            return Ok(GuardOutcome::DryRun);
+        }
+
+        Ok(GuardOutcome::Send)
+    }
+}
+
+/// The default dry-run logger: a `tracing` event with the `tracing` feature, otherwise nothing.
+///
+/// The body of the command is not recorded.
+#[cfg(feature = "tracing")]
+fn log_dry_run(context: &CommandContext) {
+    tracing::info!(
+        command = context.command.as_str(),
+        method = context.verb.as_str(),
+        vehicle_id = context.vehicle_id,
+        path = context.path,
+        "smartcar dry run, command not sent"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn log_dry_run(_context: &CommandContext) {}
+
+#[cfg(test)]
+fn test_context(command: Command, hour: u32) -> CommandContext<'static> {
+    use chrono::TimeZone;
+
+    CommandContext {
+        vehicle_id: "vehicle-1",
+        command,
+        verb: HttpVerb::Post,
+        path: "/security",
+        body: None,
+        timestamp: Utc
+            .with_ymd_and_hms(2022, 9, 5, hour, 0, 0)
+            .unwrap(),
+    }
+}
+
+#[test]
+fn test_command_rules_first_match_wins() {
+    let night = TimeWindow::new(
+        NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+    );
+    let rules = CommandRules::new(PolicyDecision::Allow)
+        .add_rule(
+            CommandRule::new(PolicyDecision::Deny("no unlocking at night".to_string()))
+                .set_command(Command::Unlock)
+                .set_time_window(night),
+        )
+        .add_rule(
+            CommandRule::new(PolicyDecision::RequireConfirmation)
+                .set_command(Command::Unlock)
+                .set_vehicle_id("vehicle-1"),
+        );
+
+    assert_eq!(
+        rules.decide(&test_context(Command::Unlock, 23)),
+        PolicyDecision::Deny("no unlocking at night".to_string())
+    );
+    assert_eq!(
+        rules.decide(&test_context(Command::Unlock, 12)),
+        PolicyDecision::RequireConfirmation
+    );
+    assert_eq!(
+        rules.decide(&test_context(Command::Lock, 23)),
+        PolicyDecision::Allow
+    );
+}
+
+#[test]
+fn test_command_guard_confirmation() {
+    let require = |_: &CommandContext| PolicyDecision::RequireConfirmation;
+    let context = test_context(Command::StopCharge, 12);
+
+    let without_handler = CommandGuard::new(require).check(&context);
+    assert!(matches!(without_handler, Err(Error::CommandDenied { .. })));
+
+    let declined = CommandGuard::new(require)
+        .set_confirmation(|_| false)
+        .check(&context);
+    assert!(matches!(declined, Err(Error::CommandDenied { .. })));
+
+    let confirmed = CommandGuard::new(require)
+        .set_confirmation(|_| true)
+        .set_dry_run(true)
+        .set_dry_run_logger(|_| {})
+        .check(&context);
+    assert_eq!(confirmed.unwrap(), GuardOutcome::DryRun);
+}
@@ -1,3 +1,4 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use reqwest::{RequestBuilder, Response, StatusCode};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -7,11 +8,39 @@ use crate::{
     response::{meta, Meta},
 };
 
+#[cfg(feature = "tracing")]
+mod trace;
+
+/// The characters that must be percent-encoded in a query value
+const QUERY_VALUE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Percent-encode a query value, e.g. `read_vin read_fuel` -> `read_vin%20read_fuel`
+pub(crate) fn encode_query_value(value: &str) -> String {
+    utf8_percent_encode(value, QUERY_VALUE).to_string()
+}
+
 pub(crate) trait MultiQuery {
     /// Build a vector of multiple query/value tuples
     fn vectorize(&self) -> Vec<(String, String)>;
 
-    /// Build a string with multiple query/value pairs
+    /// Build a string with multiple query/value pairs, percent-encoding the values
     ///
     /// Note, the beginning of this string will NOT include
     /// an "?" or "&" in the beginning or end.
@@ -27,7 +56,7 @@ pub(crate) trait MultiQuery {
             let (q, v) = query_vec[i].to_owned();
             query_string.push_str(&q);
             query_string.push('=');
-            query_string.push_str(&v);
+            query_string.push_str(&encode_query_value(&v));
         }
 
         query_string
@@ -46,6 +75,7 @@ pub(crate) fn get_basic_b64_auth_header(username: &str, password: &str) -> Strin
     format!("Basic {}", &encoded)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpVerb {
     Get,
     Post,
@@ -53,9 +83,24 @@ pub enum HttpVerb {
     Delete,
 }
 
+impl HttpVerb {
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpVerb::Get => "GET",
+            HttpVerb::Post => "POST",
+            HttpVerb::Put => "PUT",
+            HttpVerb::Delete => "DELETE",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct SmartcarRequestBuilder {
     request: RequestBuilder,
+    #[cfg(feature = "tracing")]
+    verb: HttpVerb,
+    #[cfg(feature = "tracing")]
+    url: String,
 }
 
 impl SmartcarRequestBuilder {
@@ -69,6 +114,10 @@ impl SmartcarRequestBuilder {
                 HttpVerb::Put => client.put(url),
                 HttpVerb::Delete => client.delete(url),
             },
+            #[cfg(feature = "tracing")]
+            verb,
+            #[cfg(feature = "tracing")]
+            url: url.to_owned(),
         }
     }
     pub(crate) fn add_header(mut self, header: &str, value: &str) -> Self {
@@ -91,8 +140,28 @@ impl SmartcarRequestBuilder {
         self
     }
 
+    #[cfg(not(feature = "tracing"))]
     pub(crate) async fn send(self) -> Result<(Response, Meta), Error> {
+        self.send_once().await
+    }
+
+    /// Send the request inside a `smartcar.request` span, see the `trace` module
+    #[cfg(feature = "tracing")]
+    pub(crate) async fn send(self) -> Result<(Response, Meta), Error> {
+        use tracing::Instrument;
+
+        let span = trace::request_span(self.verb, &self.url);
+        let started = std::time::Instant::now();
+        let result = self.send_once().instrument(span.clone()).await;
+        trace::record_result(&span, &result, started.elapsed());
+
+        result
+    }
+
+    async fn send_once(self) -> Result<(Response, Meta), Error> {
         let res = self.request.send().await?;
+        #[cfg(feature = "tracing")]
+        trace::record_response(&res);
 
         if res.status() != StatusCode::OK {
             let sc_err = res.json::<SmartcarError>().await?;
@@ -0,0 +1,145 @@
+//! `tracing` instrumentation of the requests sent to Smartcar.
+//!
+//! Only the method, the path (without its query), the vehicle id, and the response
+//! are recorded. Headers, query values, and bodies (and so tokens and secrets) never are.
+
+use std::time::Duration;
+
+use reqwest::Response;
+use tracing::{field, Span};
+
+use super::HttpVerb;
+use crate::error::Error;
+use crate::response::Meta;
+
+/// A span for a single request, with the ids in its path templated (see `templated_path`)
+pub(super) fn request_span(verb: HttpVerb, url: &str) -> Span {
+    let (path, vehicle_id) = templated_path(url);
+
+    tracing::info_span!(
+        "smartcar.request",
+        method = verb.as_str(),
+        path = %path,
+        vehicle_id = vehicle_id.as_deref(),
+        status = field::Empty,
+        latency_ms = field::Empty,
+        request_id = field::Empty,
+        error_type = field::Empty,
+    )
+}
+
+/// Record the status and `SC-Request-Id` of a response on the current span
+pub(super) fn record_response(res: &Response) {
+    let span = Span::current();
+    span.record("status", res.status().as_u16());
+    if let Some(request_id) = res
+        .headers()
+        .get("SC-Request-Id")
+        .and_then(|h| h.to_str().ok())
+    {
+        span.record("request_id", request_id);
+    }
+}
+
+pub(super) fn record_result(
+    span: &Span,
+    result: &Result<(Response, Meta), Error>,
+    latency: Duration,
+) {
+    span.record("latency_ms", latency.as_millis() as u64);
+
+    match result {
+        Ok(_) => {
+            tracing::debug!(parent: span, "smartcar request succeeded");
+        }
+        Err(err) => {
+            let error_type = match err {
+                Error::SmartcarError(e) => {
+                    span.record("request_id", e.request_id.as_str());
+                    e.error_type.as_str()
+                }
+                Error::SdkReqwestFailure(_) => "SDK_HTTP_FAILURE",
+                _ => "SDK_ERROR",
+            };
+            span.record("error_type", error_type);
+            tracing::warn!(parent: span, error_type, "smartcar request failed");
+        }
+    }
+}
+
+/// The path of a URL with its ids templated, and the vehicle id
+///
+/// The segment after `vehicles` becomes `{vehicle_id}`, the segment after `webhooks` becomes
+/// `{webhook_id}`, and any other UUID-shaped segment (e.g. in paths passed to `Vehicle::request`)
+/// becomes `{id}`, so that span names do not grow with every id.
+fn templated_path(url: &str) -> (String, Option<String>) {
+    let path = match url::Url::parse(url) {
+        Ok(u) => u.path().to_owned(),
+        Err(_) => return (String::from("{unknown}"), None),
+    };
+
+    let mut vehicle_id = None;
+    let mut previous = "";
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(|segment| {
+            let templated = match previous {
+                "vehicles" if !segment.is_empty() => {
+                    vehicle_id = Some(segment.to_owned());
+                    "{vehicle_id}"
+                }
+                "webhooks" if !segment.is_empty() => "{webhook_id}",
+                _ if is_uuid(segment) => "{id}",
+                _ => segment,
+            };
+            previous = segment;
+            templated
+        })
+        .collect();
+
+    (segments.join("/"), vehicle_id)
+}
+
+/// Returns true for segments like `36b8d60e-4c1a-4f2b-9e3d-0a1b2c3d4e5f`
+fn is_uuid(segment: &str) -> bool {
+    segment.len() == 36
+        && segment.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[test]
+fn test_templated_path() {
+    assert_eq!(
+        templated_path("https://api.smartcar.com/v2.0/vehicles/abc-123/battery?vin=secret"),
+        (
+            String::from("/v2.0/vehicles/{vehicle_id}/battery"),
+            Some(String::from("abc-123"))
+        )
+    );
+    assert_eq!(
+        templated_path("https://api.smartcar.com/v2.0/vehicles"),
+        (String::from("/v2.0/vehicles"), None)
+    );
+    assert_eq!(
+        templated_path("https://management.smartcar.com/v2.0/management/connections/"),
+        (String::from("/v2.0/management/connections/"), None)
+    );
+    assert_eq!(
+        templated_path("https://api.smartcar.com/v2.0/vehicles/abc-123/webhooks/webhook-1"),
+        (
+            String::from("/v2.0/vehicles/{vehicle_id}/webhooks/{webhook_id}"),
+            Some(String::from("abc-123"))
+        )
+    );
+    assert_eq!(
+        templated_path(
+            "https://api.smartcar.com/v2.0/vehicles/abc-123/tesla/charge/records/36b8d60e-4c1a-4f2b-9e3d-0a1b2c3d4e5f"
+        ),
+        (
+            String::from("/v2.0/vehicles/{vehicle_id}/tesla/charge/records/{id}"),
+            Some(String::from("abc-123"))
+        )
+    );
+}
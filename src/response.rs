@@ -3,8 +3,11 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 use crate::error::SmartcarError;
+use crate::secret::Secret;
+use crate::Permission;
 
 pub(crate) mod batch;
 pub(crate) mod meta;
@@ -18,9 +21,9 @@ pub(crate) mod meta;
 /// [More info on Authorization](https://smartcar.com/docs/api/#authorization)
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Access {
-    pub access_token: String,
+    pub access_token: Secret,
     pub expires_in: i32,
-    pub refresh_token: String,
+    pub refresh_token: Secret,
     pub token_type: String,
 }
 
@@ -35,6 +38,19 @@ pub struct ApplicationPermissions {
     pub paging: Paging,
 }
 
+impl ApplicationPermissions {
+    /// The granted permissions, parsed from their names
+    pub fn permission_set(&self) -> HashSet<Permission> {
+        self.permissions
+            .iter()
+            .map(|name| {
+                name.parse()
+                    .unwrap_or_else(|never| match never {})
+            })
+            .collect()
+    }
+}
+
 /// The remaining life span of a vehicle’s engine oil
 ///
 /// This is the struct representation for the response body of
@@ -207,10 +223,10 @@ pub struct User {
 ///
 /// Commands include:
 /// - Lock/Unlock Doors
-/// **POST** `https://api.smartcar.com/v2.0/security`
+///   **POST** `https://api.smartcar.com/v2.0/security`
 ///
 /// - Stop/Start Charge
-/// **POST** `https://api.smartcar.com/v2.0/charge`
+///   **POST** `https://api.smartcar.com/v2.0/charge`
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Action {
     pub message: String,
@@ -248,7 +264,7 @@ pub struct Subscribe {
 /// and is thus nested in the capabilties field of the Compatibility struct.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Capability {
-    pub permission: String,
+    pub permission: Permission,
     pub endpoint: String,
     pub capable: bool,
     pub reason: Option<String>,
@@ -259,6 +275,8 @@ pub struct Capability {
 ///
 /// This is the struct representation for the response body of
 /// **GET** `https://api.smartcar.com/v2.0/compatibility?vin={vin}&scope={scope}&country={country}`
+///
+/// `get_compatibility_by_make` also returns this struct, built from the Compatibility Matrix.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Compatibility {
     pub compatible: bool,
@@ -266,17 +284,42 @@ pub struct Compatibility {
     pub capabilities: Vec<Capability>,
 }
 
+/// The permissions that a range of model years of a single model is capable of.
+///
+/// This struct is a part of the Compatibility Matrix response, which lists
+/// these models for each make, e.g. `{ "TESLA": [CompatibilityMatrixModel] }`.
+///
+/// This is the struct representation for the items in the response body of
+/// **GET** `https://api.smartcar.com/v2.0/compatibility/matrix?region={region}&make={make}&scope={scope}`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityMatrixModel {
+    pub model: String,
+    pub start_year: i32,
+    /// `None` for models that are still in production
+    pub end_year: Option<i32>,
+    /// The engine type, e.g. `"ICE"`, `"HEV"`, `"PHEV"`, or `"BEV"`
+    #[serde(rename = "type")]
+    pub engine_type: String,
+    pub endpoints: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+/// The cursor of the next page of a list.
+///
+/// There are no more pages if the cursor is `None`.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PagingCursor {
     pub cursor: Option<String>,
 }
 
+/// A vehicle that is connected to the application
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetConnection {
     pub user_id: String,
     pub vehicle_id: String,
-    pub connected_at: String,
+    pub connected_at: DateTime<Utc>,
     pub mode: String,
 }
 
@@ -373,3 +416,22 @@ pub struct BatchResponse {
 pub struct Batch {
     pub responses: Vec<BatchResponse>,
 }
+
+#[test]
+fn test_get_connections_parses_connected_at() {
+    let body = r#"{
+        "connections": [
+            {
+                "userId": "user-1",
+                "vehicleId": "vehicle-1",
+                "connectedAt": "2022-09-05T19:57:31.037Z",
+                "mode": "test"
+            }
+        ],
+        "paging": { "cursor": "next-page" }
+    }"#;
+    let data: GetConnections = serde_json::from_str(body).unwrap();
+
+    assert_eq!(data.connections[0].connected_at.timestamp(), 1662407851);
+    assert_eq!(data.paging.cursor, Some("next-page".to_string()));
+}
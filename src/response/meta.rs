@@ -1,4 +1,4 @@
-use chrono::{TimeZone, Utc};
+use chrono::NaiveDateTime;
 use reqwest::header::HeaderMap;
 
 use super::Meta;
@@ -14,7 +14,7 @@ pub(crate) fn generate_meta_from_headers(headers: &HeaderMap) -> Meta {
         // e.g. format, "2022-09-05T19:57:31.037Z"
         let format = "%Y-%m-%dT%H:%M:%S%.3fZ";
         let date_str = h.to_str().expect("a string");
-        let data_age = Utc.datetime_from_str(date_str, format);
+        let data_age = NaiveDateTime::parse_from_str(date_str, format).map(|d| d.and_utc());
 
         if let Ok(v) = data_age {
             meta.data_age = Some(v);
@@ -0,0 +1,86 @@
+//! A wrapper for credentials that keeps them out of logs.
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+/// A client secret, AMT, access token, or refresh token
+///
+/// `Debug` and `Display` print `[REDACTED]`, the value is zeroed when dropped,
+/// and reading it requires an explicit call to `expose`.
+///
+/// Serializes as the plain string, so that structs like `Access` can still be stored.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Secret {
+        Secret(value.into())
+    }
+
+    /// The value of the secret
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Secret {
+        Secret(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Secret {
+        Secret(value.to_owned())
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Secret, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[test]
+fn test_secret_redacts_but_serializes() {
+    let access: crate::response::Access = serde_json::from_str(
+        r#"{"access_token":"at-123","expires_in":7200,"refresh_token":"rt-456","token_type":"Bearer"}"#,
+    )
+    .unwrap();
+
+    let debug = format!("{:?}", access);
+    assert!(!debug.contains("at-123"));
+    assert!(!debug.contains("rt-456"));
+    assert_eq!(access.access_token.to_string(), "[REDACTED]");
+    assert_eq!(access.access_token.expose(), "at-123");
+
+    let json = serde_json::to_string(&access).unwrap();
+    assert!(json.contains("\"access_token\":\"at-123\""));
+    assert!(json.contains("\"refresh_token\":\"rt-456\""));
+}
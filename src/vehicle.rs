@@ -1,13 +1,25 @@
 //! This module includes the the Vehicle struct, which is responsible
 //! for getting data from and sending comands to a vehicle.
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
 
+use chrono::Utc;
+use futures::{Stream, TryStreamExt};
 use reqwest::Response;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio::sync::OnceCell;
 
+use crate::audit::{AuditOutcome, AuditRecord, AuditTrail};
+use crate::endpoints::Endpoints;
 use crate::error::Error;
-use crate::helpers::get_api_url;
+use crate::management::ManagementClient;
+use crate::paging::offset_stream;
+use crate::policy::{CommandContext, CommandGuard, GuardOutcome};
 use crate::request::{get_bearer_token_header, HttpVerb, SmartcarRequestBuilder};
 use crate::response::batch::build_batch_request_body;
 use crate::response::{
@@ -15,18 +27,75 @@ use crate::response::{
     ChargingStatus, EngineOilLife, FuelTank, Location, LockStatus, Meta, Odometer, Status,
     Subscribe, TirePressure, VehicleAttributes, Vin,
 };
+use crate::secret::Secret;
+use crate::{Permission, ScopeBuilder};
 
-#[derive(Debug)]
+/// The number of permissions requested per page by `Vehicle::permissions_stream`
+const PERMISSIONS_PAGE_LIMIT: i32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum UnitSystem {
     Imperial,
     Metric,
 }
 
+/// A remote command that can be sent to a vehicle
+///
+/// `Request` is any non-GET request sent with the general purpose `Vehicle::request` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Lock,
+    Unlock,
+    StartCharge,
+    StopCharge,
+    SetChargeLimit,
+    Request,
+}
+
+impl Command {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Command::Lock => "lock",
+            Command::Unlock => "unlock",
+            Command::StartCharge => "start_charge",
+            Command::StopCharge => "stop_charge",
+            Command::SetChargeLimit => "set_charge_limit",
+            Command::Request => "request",
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A vehicle that your application has access to
+///
+/// Create one with `Vehicle::new`, since more fields may be added.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct Vehicle {
     pub id: String,
-    pub access_token: String,
+    pub access_token: Secret,
     pub unit_system: UnitSystem,
+
+    /// Default: `Endpoints::default()`
+    pub endpoints: Endpoints,
+
+    /// Checks every command before it is sent to the vehicle
+    pub command_guard: Option<Arc<CommandGuard>>,
+
+    /// Records every command sent to the vehicle
+    pub audit_trail: Option<AuditTrail>,
+
+    /// Check the granted permissions locally before calling an endpoint
+    pub permission_preflight: bool,
+
+    granted_permissions: OnceCell<HashSet<Permission>>,
 }
 
 impl Vehicle {
@@ -34,33 +103,69 @@ impl Vehicle {
     pub fn new(vehicle_id: &str, access_token: &str) -> Vehicle {
         Vehicle {
             id: vehicle_id.to_owned(),
-            access_token: access_token.to_owned(),
+            access_token: Secret::from(access_token),
             unit_system: UnitSystem::Metric,
+            endpoints: Endpoints::default(),
+            command_guard: None,
+            audit_trail: None,
+            permission_preflight: false,
+            granted_permissions: OnceCell::new(),
         }
     }
 
+    /// Run every command (lock, unlock, start/stop charge, set charge limit, and
+    /// non-GET requests sent with `Vehicle::request`) through a `CommandGuard`.
+    ///
+    /// The same guard can be shared by many vehicles.
+    pub fn set_command_guard(mut self, guard: Arc<CommandGuard>) -> Self {
+        self.command_guard = Some(guard);
+        self
+    }
+
+    /// Record every command (lock, unlock, start/stop charge, set charge limit, and
+    /// non-GET requests sent with `Vehicle::request`) to an audit trail.
+    pub fn set_audit_trail(mut self, trail: AuditTrail) -> Self {
+        self.audit_trail = Some(trail);
+        self
+    }
+
+    /// Before calling an endpoint, check that its permission was granted to your application,
+    /// returning `Error::MissingPermission` without sending the request if it was not.
+    ///
+    /// The granted permissions are fetched once, on the first call, and then cached.
+    /// `Vehicle::request` and `Vehicle::batch` are not checked.
+    pub fn set_permission_preflight(mut self, enabled: bool) -> Self {
+        self.permission_preflight = enabled;
+        self
+    }
+
+    /// Send requests to these endpoints instead of `Endpoints::default()`
+    pub fn set_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
     fn get_request_builder(&self, path: &str, verb: HttpVerb) -> SmartcarRequestBuilder {
         let url = format!(
             "{api_url}/v2.0/vehicles/{id}{path}",
-            api_url = get_api_url(),
+            api_url = self.endpoints.api,
             id = self.id,
             path = path
         );
 
         SmartcarRequestBuilder::new(&url, verb).add_header(
             "Authorization",
-            &get_bearer_token_header(&self.access_token),
+            &get_bearer_token_header(self.access_token.expose()),
         )
     }
 
-    /// General purpose request method
-    pub async fn request(
+    fn get_custom_request_builder(
         &self,
         path: &str,
         verb: HttpVerb,
         body: Option<Value>,
         headers: Option<HashMap<String, String>>,
-    ) -> Result<(Response, Meta), Error> {
+    ) -> SmartcarRequestBuilder {
         let mut request_builder = self.get_request_builder(path, verb);
 
         if let Some(request_body) = body {
@@ -72,30 +177,220 @@ impl Vehicle {
             }
         }
 
-        let (res, meta) = request_builder.send().await?;
+        request_builder
+    }
+
+    /// General purpose request method
+    pub async fn request(
+        &self,
+        path: &str,
+        verb: HttpVerb,
+        body: Option<Value>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<(Response, Meta), Error> {
+        if verb != HttpVerb::Get {
+            return self
+                .send_command(Command::Request, None, path, verb, body, headers)
+                .await;
+        }
+
+        let (res, meta) = self
+            .get_custom_request_builder(path, verb, body, headers)
+            .send()
+            .await?;
 
         Ok((res, meta))
     }
 
+    /// Send a command to the vehicle, after checking its permission (with the permission
+    /// preflight, if enabled) and the command guard (if any), and record it to the audit
+    /// trail (if any).
+    ///
+    /// In dry-run mode, the returned response is a stand-in `Action` with a `DRY_RUN` status.
+    async fn send_command(
+        &self,
+        command: Command,
+        permission: Option<Permission>,
+        path: &str,
+        verb: HttpVerb,
+        body: Option<Value>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<(Response, Meta), Error> {
+        let timestamp = Utc::now();
+        let preflight = match permission {
+            Some(permission) => self.require_permission(permission).await,
+            None => Ok(()),
+        };
+        let guard_outcome = match (preflight, &self.command_guard) {
+            (Err(e), _) => Err(e),
+            (Ok(()), Some(guard)) => guard.check(&CommandContext {
+                vehicle_id: &self.id,
+                command,
+                verb,
+                path,
+                body: body.as_ref(),
+                timestamp,
+            }),
+            (Ok(()), None) => Ok(GuardOutcome::Send),
+        };
+
+        let dry_run = matches!(guard_outcome, Ok(GuardOutcome::DryRun));
+        let result = match guard_outcome {
+            Ok(GuardOutcome::Send) => {
+                self.get_custom_request_builder(path, verb, body.clone(), headers)
+                    .send()
+                    .await
+            }
+            Ok(GuardOutcome::DryRun) => Ok(dry_run_response(command)),
+            Err(e) => Err(e),
+        };
+
+        if let Some(trail) = &self.audit_trail {
+            let (outcome, request_id) = match &result {
+                Ok(_) if dry_run => (AuditOutcome::DryRun, None),
+                Ok((_, meta)) => (AuditOutcome::Success, meta.request_id.to_owned()),
+                Err(Error::CommandDenied { reason, .. }) => (
+                    AuditOutcome::Denied {
+                        reason: reason.to_owned(),
+                    },
+                    None,
+                ),
+                Err(e @ Error::MissingPermission(_)) => (
+                    AuditOutcome::Denied {
+                        reason: e.to_string(),
+                    },
+                    None,
+                ),
+                Err(e) => {
+                    let request_id = match e {
+                        Error::SmartcarError(sc_err) => Some(sc_err.request_id.to_owned()),
+                        _ => None,
+                    };
+                    (
+                        AuditOutcome::Failed {
+                            error: e.to_string(),
+                        },
+                        request_id,
+                    )
+                }
+            };
+
+            trail.record(AuditRecord {
+                vehicle_id: self.id.to_owned(),
+                command,
+                method: verb.as_str().to_owned(),
+                path: path.to_owned(),
+                body,
+                timestamp,
+                outcome,
+                request_id,
+                actor: trail.actor().to_owned(),
+            });
+        }
+
+        result
+    }
+
     /// Returns a list of the permissions that have been granted to your application
     /// in relation to this vehicle
     ///
     /// [GET - Application Permissions](https://smartcar.com/docs/api-reference/application-permissions)
     pub async fn permissions(&self) -> Result<(ApplicationPermissions, Meta), Error> {
+        self.permissions_with_paging(None, None).await
+    }
+
+    /// Returns a page of the permissions that have been granted to your application
+    /// in relation to this vehicle
+    ///
+    /// [GET - Application Permissions](https://smartcar.com/docs/api-reference/application-permissions)
+    pub async fn permissions_with_paging(
+        &self,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<(ApplicationPermissions, Meta), Error> {
         let path = "/permissions";
-        let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Get)
-            .send()
-            .await?;
+        let mut req = self.get_request_builder(path, HttpVerb::Get);
+
+        if let Some(l) = limit {
+            req = req.add_query("limit", &l.to_string());
+        }
+        if let Some(o) = offset {
+            req = req.add_query("offset", &o.to_string());
+        }
+
+        let (res, meta) = req.send().await?;
         let data = res.json::<ApplicationPermissions>().await?;
 
         Ok((data, meta))
     }
 
+    /// Stream all of the permissions that have been granted to your application
+    /// in relation to this vehicle, requesting each page as needed
+    ///
+    /// Use `paging::collect_all` to get every permission at once.
+    pub fn permissions_stream(&self) -> impl Stream<Item = Result<String, Error>> + '_ {
+        offset_stream(move |offset| async move {
+            let (data, _) = self
+                .permissions_with_paging(Some(PERMISSIONS_PAGE_LIMIT), Some(offset))
+                .await?;
+            Ok((data.permissions, data.paging))
+        })
+    }
+
+    /// The permissions granted to your application in relation to this vehicle,
+    /// fetched with every page of `Vehicle::permissions` on the first call, then cached.
+    pub async fn granted_permissions(&self) -> Result<&HashSet<Permission>, Error> {
+        self.granted_permissions
+            .get_or_try_init(|| async {
+                let names: Vec<String> = self.permissions_stream().try_collect().await?;
+                let granted = names
+                    .iter()
+                    .map(|name| {
+                        name.parse()
+                            .unwrap_or_else(|never| match never {})
+                    })
+                    .collect();
+                Ok(granted)
+            })
+            .await
+    }
+
+    /// The permissions of `scope` that have not been granted to your application,
+    /// in the order they were added to `scope`.
+    ///
+    /// If any are missing, send the user back through Smartcar Connect with `scope`.
+    pub async fn missing_permissions(
+        &self,
+        scope: &ScopeBuilder,
+    ) -> Result<Vec<Permission>, Error> {
+        let granted = self.granted_permissions().await?;
+
+        Ok(scope
+            .ordered_permissions()
+            .filter(|p| !granted.contains(p))
+            .collect())
+    }
+
+    /// With permission preflight enabled, return an error if `permission` was not granted
+    async fn require_permission(&self, permission: Permission) -> Result<(), Error> {
+        if self.permission_preflight
+            && !self
+                .granted_permissions()
+                .await?
+                .contains(&permission)
+        {
+            return Err(Error::MissingPermission(permission));
+        }
+
+        Ok(())
+    }
+
     /// Returns the remaining life span of a vehicle’s engine oil.
     ///
     /// [GET - Engine Oil](https://smartcar.com/docs/api-reference/get-engine-oil-life)
     pub async fn engine_oil(&self) -> Result<(EngineOilLife, Meta), Error> {
+        self.require_permission(Permission::ReadEngineOil)
+            .await?;
         let path = "/engine/oil";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -110,6 +405,8 @@ impl Vehicle {
     ///
     /// [GET - EV Battery Capacity](https://smartcar.com/docs/api-reference/evs/get-battery-capacity)
     pub async fn battery_capacity(&self) -> Result<(BatteryCapacity, Meta), Error> {
+        self.require_permission(Permission::ReadBattery)
+            .await?;
         let path = "/battery/capacity";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -124,6 +421,8 @@ impl Vehicle {
     ///
     /// [GET - EV Battery Level](https://smartcar.com/docs/api-reference/evs/get-battery-level)
     pub async fn battery_level(&self) -> Result<(BatteryLevel, Meta), Error> {
+        self.require_permission(Permission::ReadBattery)
+            .await?;
         let path = "/battery";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -138,6 +437,8 @@ impl Vehicle {
     ///
     /// [GET - EV Charging Status](https://smartcar.com/docs/api-reference/evs/get-charge-status)
     pub async fn charging_status(&self) -> Result<(ChargingStatus, Meta), Error> {
+        self.require_permission(Permission::ReadCharge)
+            .await?;
         let path = "/charge";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -152,6 +453,8 @@ impl Vehicle {
     ///
     /// [GET - EV Charge Limit](https://smartcar.com/docs/api-reference/evs/get-charge-limit)
     pub async fn charge_limit(&self) -> Result<(ChargeLimit, Meta), Error> {
+        self.require_permission(Permission::ReadCharge)
+            .await?;
         let path = "/charge/limit";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -167,6 +470,8 @@ impl Vehicle {
     ///
     /// [GET - Fuel Tank](https://smartcar.com/docs/api-reference/get-fuel-tank)
     pub async fn fuel_tank(&self) -> Result<(FuelTank, Meta), Error> {
+        self.require_permission(Permission::ReadFuel)
+            .await?;
         let path = "/fuel";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -181,6 +486,8 @@ impl Vehicle {
     ///
     /// [GET - Location](https://smartcar.com/docs/api-reference/get-location)
     pub async fn location(&self) -> Result<(Location, Meta), Error> {
+        self.require_permission(Permission::ReadLocation)
+            .await?;
         let path = "/location";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -195,6 +502,8 @@ impl Vehicle {
     ///
     /// [GET - Odometer](https://smartcar.com/docs/api-reference/get-odometer)
     pub async fn odometer(&self) -> Result<(Odometer, Meta), Error> {
+        self.require_permission(Permission::ReadOdometer)
+            .await?;
         let path = "/odometer";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -209,6 +518,8 @@ impl Vehicle {
     ///
     /// [GET - Tire Pressure](https://smartcar.com/docs/api-reference/get-tire-pressure)
     pub async fn tire_pressure(&self) -> Result<(TirePressure, Meta), Error> {
+        self.require_permission(Permission::ReadTires)
+            .await?;
         let path = "/tires/pressure";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -224,6 +535,8 @@ impl Vehicle {
     ///
     /// [GET - Lock Status](https://smartcar.com/docs/api-reference/get-lock-status)
     pub async fn lock_status(&self) -> Result<(LockStatus, Meta), Error> {
+        self.require_permission(Permission::ReadSecurity)
+            .await?;
         let path = "/security";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -238,6 +551,8 @@ impl Vehicle {
     ///
     /// [GET - Vehicle Info](https://smartcar.com/docs/api-reference/get-vehicle-info)
     pub async fn attributes(&self) -> Result<(VehicleAttributes, Meta), Error> {
+        self.require_permission(Permission::ReadVehicleInfo)
+            .await?;
         let path = "/";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -252,6 +567,8 @@ impl Vehicle {
     ///
     /// [GET - VIN](https://smartcar.com/docs/api-reference/get-vin)
     pub async fn vin(&self) -> Result<(Vin, Meta), Error> {
+        self.require_permission(Permission::ReadVin)
+            .await?;
         let path = "/vin";
         let (res, meta) = self
             .get_request_builder(path, HttpVerb::Get)
@@ -269,9 +586,14 @@ impl Vehicle {
         let path = "/security";
         let req_body = json!({ "action": "LOCK"});
         let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Post)
-            .add_body(req_body)
-            .send()
+            .send_command(
+                Command::Lock,
+                Some(Permission::ControlSecurity),
+                path,
+                HttpVerb::Post,
+                Some(req_body),
+                None,
+            )
             .await?;
         let data = res.json::<Action>().await?;
 
@@ -285,9 +607,14 @@ impl Vehicle {
         let path = "/security";
         let req_body = json!({ "action": "UNLOCK"});
         let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Post)
-            .add_body(req_body)
-            .send()
+            .send_command(
+                Command::Unlock,
+                Some(Permission::ControlSecurity),
+                path,
+                HttpVerb::Post,
+                Some(req_body),
+                None,
+            )
             .await?;
         let data = res.json::<Action>().await?;
 
@@ -301,9 +628,14 @@ impl Vehicle {
         let path = "/charge";
         let req_body = json!({ "action": "START"});
         let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Post)
-            .add_body(req_body)
-            .send()
+            .send_command(
+                Command::StartCharge,
+                Some(Permission::ControlCharge),
+                path,
+                HttpVerb::Post,
+                Some(req_body),
+                None,
+            )
             .await?;
         let data = res.json::<Action>().await?;
 
@@ -317,9 +649,14 @@ impl Vehicle {
         let path = "/charge";
         let req_body = json!({ "action": "STOP"});
         let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Post)
-            .add_body(req_body)
-            .send()
+            .send_command(
+                Command::StopCharge,
+                Some(Permission::ControlCharge),
+                path,
+                HttpVerb::Post,
+                Some(req_body),
+                None,
+            )
             .await?;
         let data = res.json::<Action>().await?;
 
@@ -333,9 +670,14 @@ impl Vehicle {
         let path = "/charge/limit";
         let req_body = json!({ "limit": limit });
         let (res, meta) = self
-            .get_request_builder(path, HttpVerb::Post)
-            .add_body(req_body)
-            .send()
+            .send_command(
+                Command::SetChargeLimit,
+                Some(Permission::ControlCharge),
+                path,
+                HttpVerb::Post,
+                Some(req_body),
+                None,
+            )
             .await?;
         let data = res.json::<Action>().await?;
 
@@ -393,25 +735,104 @@ impl Vehicle {
     /// - `webhook_id` - The id of the webhook, found in your dashboard
     ///
     /// [DELETE - Unsubscribe from Webhook](https://smartcar.com/docs/api-reference/webhooks/unsubscribe-webhook)
-    pub async fn unsubscribe(
-        &self,
-        amt: &str,
-        webhook_id: &str,
-    ) -> Result<(Subscribe, Meta), Error> {
-        let url = format!(
-            "{api_url}/v2.0/vehicles/{id}/webhooks/{webhook_id}",
-            api_url = get_api_url(),
-            id = self.id,
-            webhook_id = webhook_id
-        );
+    pub async fn unsubscribe(&self, amt: &str, webhook_id: &str) -> Result<(Status, Meta), Error> {
+        ManagementClient::new(amt)
+            .set_endpoints(self.endpoints.to_owned())
+            .unsubscribe(&self.id, webhook_id)
+            .await
+    }
+}
 
-        // Different bearer token requires a request built from scratch,
-        let (res, meta) = SmartcarRequestBuilder::new(&url, HttpVerb::Delete)
-            .add_header("Authorization", &get_bearer_token_header(amt))
-            .send()
-            .await?;
-        let data = res.json::<Subscribe>().await?;
+/// A stand-in response for a command that was not sent because of dry-run mode
+fn dry_run_response(command: Command) -> (Response, Meta) {
+    let body = json!({
+        "message": format!("dry run::{} was not sent to the vehicle", command),
+        "status": "DRY_RUN",
+    });
+    let res = Response::from(http::Response::new(body.to_string()));
+    let meta = Meta {
+        data_age: None,
+        request_id: None,
+        unit_system: None,
+    };
+
+    (res, meta)
+}
 
-        Ok((data, meta))
-    }
+#[tokio::test]
+async fn test_command_guard_dry_run_and_deny() {
+    use crate::policy::{CommandRule, CommandRules, PolicyDecision};
+
+    let rules = CommandRules::new(PolicyDecision::Allow).add_rule(
+        CommandRule::new(PolicyDecision::Deny("never unlock".to_string()))
+            .set_command(Command::Unlock),
+    );
+    let guard = CommandGuard::new(rules)
+        .set_dry_run(true)
+        .set_dry_run_logger(|_| {});
+    let v = Vehicle::new("vehicle-1", "access-token").set_command_guard(Arc::new(guard));
+
+    let (action, meta) = v.lock().await.unwrap();
+    assert_eq!(action.status, "DRY_RUN");
+    assert!(meta.request_id.is_none());
+
+    let unlock = v.unlock().await;
+    assert!(matches!(unlock, Err(Error::CommandDenied { .. })));
+}
+
+#[tokio::test]
+async fn test_permission_preflight_uses_cached_permissions() {
+    let v = Vehicle::new("vehicle-1", "access-token").set_permission_preflight(true);
+    v.granted_permissions
+        .set(HashSet::from([
+            Permission::ReadVin,
+            Permission::ReadOdometer,
+        ]))
+        .unwrap();
+
+    let location = v.location().await;
+    assert!(matches!(
+        location,
+        Err(Error::MissingPermission(Permission::ReadLocation))
+    ));
+
+    let unlock = v.unlock().await;
+    assert!(matches!(
+        unlock,
+        Err(Error::MissingPermission(Permission::ControlSecurity))
+    ));
+
+    let scope = ScopeBuilder::new().add_permissions([
+        Permission::ReadVin,
+        Permission::ReadBattery,
+        Permission::ControlCharge,
+    ]);
+    let missing = v.missing_permissions(&scope).await.unwrap();
+    assert_eq!(
+        missing,
+        [Permission::ReadBattery, Permission::ControlCharge]
+    );
+}
+
+#[tokio::test]
+async fn test_permission_preflight_denials_are_audited() {
+    use crate::audit::{AuditOutcome, AuditTrail, MemorySink};
+
+    let sink = Arc::new(MemorySink::default());
+    let v = Vehicle::new("vehicle-1", "access-token")
+        .set_permission_preflight(true)
+        .set_audit_trail(AuditTrail::new(sink.clone(), "nightly-job"));
+    v.granted_permissions
+        .set(HashSet::from([Permission::ReadVin]))
+        .unwrap();
+
+    assert!(v.set_charge_limit(0.8).await.is_err());
+
+    let records = sink.records.lock().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].command, Command::SetChargeLimit);
+    assert!(matches!(
+        &records[0].outcome,
+        AuditOutcome::Denied { reason } if reason.contains("control_charge")
+    ));
 }
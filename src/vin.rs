@@ -0,0 +1,259 @@
+//! Offline validation and decoding of Vehicle Identification Numbers (VINs).
+//!
+//! Validating a VIN before sending it to Smartcar API (e.g. with `get_compatibility`
+//! or `AuthUrlOptionsBuilder::set_single_select_by_vin`) catches typos without an API call.
+
+use chrono::{Datelike, Utc};
+
+use crate::error::Error;
+use crate::response::Vin;
+
+const VIN_LENGTH: usize = 17;
+
+/// The weight of each position of a VIN, used to compute the ISO 3779 check digit
+const CHECK_DIGIT_WEIGHTS: [u32; VIN_LENGTH] = [8, 7, 6, 5, 4, 3, 2, 10, 0, 9, 8, 7, 6, 5, 4, 3, 2];
+
+/// Makes by World Manufacturer Identifier (the first 3 characters of a VIN)
+const WMI_MAKES: [(&str, &str); 64] = [
+    ("1C3", "Chrysler"),
+    ("1C4", "Jeep"),
+    ("1C6", "Ram"),
+    ("1FA", "Ford"),
+    ("1FM", "Ford"),
+    ("1FT", "Ford"),
+    ("1G1", "Chevrolet"),
+    ("1GC", "Chevrolet"),
+    ("1GN", "Chevrolet"),
+    ("1GT", "GMC"),
+    ("1G6", "Cadillac"),
+    ("1GY", "Cadillac"),
+    ("1HG", "Honda"),
+    ("1J4", "Jeep"),
+    ("1LN", "Lincoln"),
+    ("1N4", "Nissan"),
+    ("1N6", "Nissan"),
+    ("19X", "Honda"),
+    ("19U", "Acura"),
+    ("2C3", "Chrysler"),
+    ("2HG", "Honda"),
+    ("2T1", "Toyota"),
+    ("3FA", "Ford"),
+    ("3GN", "Chevrolet"),
+    ("3VW", "Volkswagen"),
+    ("4S3", "Subaru"),
+    ("4S4", "Subaru"),
+    ("4T1", "Toyota"),
+    ("4T3", "Toyota"),
+    ("5FN", "Honda"),
+    ("5J6", "Honda"),
+    ("5N1", "Nissan"),
+    ("5NP", "Hyundai"),
+    ("5UX", "BMW"),
+    ("5YJ", "Tesla"),
+    ("7SA", "Tesla"),
+    ("7FA", "Honda"),
+    ("JF1", "Subaru"),
+    ("JF2", "Subaru"),
+    ("JHM", "Honda"),
+    ("JM1", "Mazda"),
+    ("JN1", "Nissan"),
+    ("JTD", "Toyota"),
+    ("JTH", "Lexus"),
+    ("KL4", "Buick"),
+    ("KM8", "Hyundai"),
+    ("KNA", "Kia"),
+    ("KND", "Kia"),
+    ("LRW", "Tesla"),
+    ("SAL", "Land Rover"),
+    ("SAJ", "Jaguar"),
+    ("VF1", "Renault"),
+    ("VF3", "Peugeot"),
+    ("WAU", "Audi"),
+    ("WA1", "Audi"),
+    ("WBA", "BMW"),
+    ("WBY", "BMW"),
+    ("WDD", "Mercedes-Benz"),
+    ("WMW", "MINI"),
+    ("WP0", "Porsche"),
+    ("WP1", "Porsche"),
+    ("WVW", "Volkswagen"),
+    ("WVG", "Volkswagen"),
+    ("YV1", "Volvo"),
+];
+
+/// The region of the manufacturer, from the first character of a VIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VinRegion {
+    Africa,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+/// A valid VIN, split into its sections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedVin {
+    /// The normalized (uppercase) VIN
+    pub vin: String,
+    /// World Manufacturer Identifier, characters 1-3
+    pub wmi: String,
+    /// Vehicle Descriptor Section, characters 4-9 (including the check digit)
+    pub vds: String,
+    /// Vehicle Identifier Section, characters 10-17
+    pub vis: String,
+    pub region: VinRegion,
+    /// `None` if the WMI is not a known make
+    pub make: Option<&'static str>,
+    /// The model year, from the 10th character
+    ///
+    /// The year code repeats every 30 years. For North American VINs, the 7th character
+    /// tells the cycles apart. For others, this is the latest possible year that is not
+    /// later than next year.
+    pub model_year: i32,
+}
+
+/// Check that a VIN is 17 allowed characters (ignoring case) with a valid
+/// ISO 3779 check digit for North American VINs.
+pub fn validate(vin: &str) -> Result<(), Error> {
+    decode(vin).map(|_| ())
+}
+
+/// Validate and decode a VIN, ignoring case
+pub fn decode(vin: &str) -> Result<DecodedVin, Error> {
+    let normalized = vin.trim().to_uppercase();
+    let invalid = |reason: &str| Error::InvalidVin {
+        vin: vin.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    if normalized.chars().count() != VIN_LENGTH {
+        return Err(invalid("must be 17 characters"));
+    }
+    let values: Vec<u32> = normalized
+        .chars()
+        .map(transliterate)
+        .collect::<Option<_>>()
+        .ok_or_else(|| invalid("must only contain digits and letters other than I, O, and Q"))?;
+
+    let chars: Vec<char> = normalized.chars().collect();
+    let region = region(chars[0]).ok_or_else(|| invalid("unknown region"))?;
+    let year_cycle = year_cycle(chars[9]).ok_or_else(|| invalid("invalid model year"))?;
+
+    if region == VinRegion::NorthAmerica {
+        let sum: u32 = values
+            .iter()
+            .zip(CHECK_DIGIT_WEIGHTS)
+            .map(|(v, w)| v * w)
+            .sum();
+        let expected = match sum % 11 {
+            10 => 'X',
+            d => char::from_digit(d, 10).unwrap_or('0'),
+        };
+        if chars[8] != expected {
+            return Err(invalid("check digit does not match"));
+        }
+    }
+
+    let model_year = if region == VinRegion::NorthAmerica {
+        match chars[6].is_ascii_alphabetic() {
+            true => year_cycle + 30,
+            false => year_cycle,
+        }
+    } else {
+        let latest = Utc::now().year() + 1;
+        let mut year = year_cycle;
+        while year + 30 <= latest {
+            year += 30;
+        }
+        year
+    };
+
+    let wmi: String = normalized[..3].to_owned();
+    let make = WMI_MAKES
+        .iter()
+        .find(|(prefix, _)| *prefix == wmi)
+        .map(|(_, make)| *make);
+
+    Ok(DecodedVin {
+        vds: normalized[3..9].to_owned(),
+        vis: normalized[9..].to_owned(),
+        vin: normalized,
+        wmi,
+        region,
+        make,
+        model_year,
+    })
+}
+
+impl Vin {
+    /// Validate and decode the VIN returned by Smartcar API
+    pub fn decode(&self) -> Result<DecodedVin, Error> {
+        decode(&self.vin)
+    }
+}
+
+/// The value of a VIN character, or None if it is not allowed
+fn transliterate(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => c.to_digit(10),
+        'A'..='H' => Some(c as u32 - 'A' as u32 + 1),
+        'J'..='N' => Some(c as u32 - 'J' as u32 + 1),
+        'P' => Some(7),
+        'R' => Some(9),
+        'S'..='Z' => Some(c as u32 - 'S' as u32 + 2),
+        _ => None,
+    }
+}
+
+fn region(c: char) -> Option<VinRegion> {
+    match c {
+        'A'..='H' => Some(VinRegion::Africa),
+        'J'..='R' => Some(VinRegion::Asia),
+        'S'..='Z' => Some(VinRegion::Europe),
+        '1'..='5' => Some(VinRegion::NorthAmerica),
+        '6' | '7' => Some(VinRegion::Oceania),
+        '8' | '9' => Some(VinRegion::SouthAmerica),
+        _ => None,
+    }
+}
+
+/// The model year of the 1980-2009 cycle for the 10th character of a VIN
+fn year_cycle(c: char) -> Option<i32> {
+    const CODES: &str = "ABCDEFGHJKLMNPRSTVWXY123456789";
+    CODES.find(c).map(|i| 1980 + i as i32)
+}
+
+#[test]
+fn test_decode_north_american_vins() {
+    let honda = decode("1hgcm82633a004352").unwrap();
+    assert_eq!(honda.vin, "1HGCM82633A004352");
+    assert_eq!(honda.wmi, "1HG");
+    assert_eq!(honda.vds, "CM8263");
+    assert_eq!(honda.vis, "3A004352");
+    assert_eq!(honda.region, VinRegion::NorthAmerica);
+    assert_eq!(honda.make, Some("Honda"));
+    assert_eq!(honda.model_year, 2003);
+
+    let tesla = decode("5YJ3E1EA2KF317000").unwrap();
+    assert_eq!(tesla.make, Some("Tesla"));
+    assert_eq!(tesla.model_year, 2019);
+
+    // Check digit is X
+    assert!(validate("1M8GDM9AXKP042788").is_ok());
+}
+
+#[test]
+fn test_invalid_vins() {
+    // Check digit should be 2
+    assert!(validate("5YJ3E1EA7KF317000").is_err());
+    // Too short
+    assert!(validate("5YJ3E1EA2KF31700").is_err());
+    // Contains an O
+    assert!(validate("5YJ3E1EA2KF3170O0").is_err());
+    // Check digit is not enforced outside of North America
+    let bmw = decode("WBA3A5C51CF256651").unwrap();
+    assert_eq!(bmw.region, VinRegion::Europe);
+    assert_eq!(bmw.model_year, 2012);
+}
@@ -8,18 +8,82 @@ type HmacSha256 = Hmac<Sha256>;
 
 use crate::error::Error;
 
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod payload;
+pub mod processor;
+pub mod replay;
+pub mod subscriptions;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+use payload::WebhookPayload;
+
 /// Generate hash challenege for webhooks.
+///
+/// This is the hex encoded HMAC-SHA256 of the challenge, keyed with your AMT.
+///
+/// Since 2.0.0. Before, the challenge was the key and the AMT was hashed, so the output
+/// for the same inputs has changed (see CHANGELOG.md).
 pub fn hash_challenge(amt: &str, challenge: &str) -> Result<String, Error> {
-    let mut mac = HmacSha256::new_from_slice(challenge.as_bytes())?;
-    mac.update(amt.as_bytes());
-    let mac_bytes = mac.finalize().into_bytes();
+    let mac_bytes = get_mac(amt, challenge)?.finalize().into_bytes();
 
     Ok(hex::encode(mac_bytes))
 }
 
-/// Verify webhook payload with AMT and signature.
+/// Verify webhook payload with AMT and signature (the `SC-Signature` header).
+///
+/// The comparison is done in constant time. The signature is hex decoded
+/// case-insensitively, and `Error::MalformedWebhookSignature` is returned if it is not
+/// a hex encoded HMAC-SHA256.
 pub fn verify_payload(amt: &str, signature: &str, body: &str) -> Result<bool, Error> {
-    Ok(hash_challenge(amt, body)? == *signature)
+    verify_payload_with_amts(&[amt], signature, body)
+}
+
+/// Verify webhook payload with signature (the `SC-Signature` header), accepting
+/// a signature from any of the AMTs.
+///
+/// Use this while rotating your AMT, to accept deliveries signed with either the old or new AMT.
+pub fn verify_payload_with_amts(amts: &[&str], signature: &str, body: &str) -> Result<bool, Error> {
+    let signature_bytes = decode_signature(signature)?;
+
+    for amt in amts {
+        if get_mac(amt, body)?
+            .verify_slice(&signature_bytes)
+            .is_ok()
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn get_mac(amt: &str, message: &str) -> Result<HmacSha256, Error> {
+    let mut mac = HmacSha256::new_from_slice(amt.as_bytes())?;
+    mac.update(message.as_bytes());
+
+    Ok(mac)
+}
+
+fn decode_signature(signature: &str) -> Result<Vec<u8>, Error> {
+    let bytes = hex::decode(signature.trim()).map_err(|_| Error::MalformedWebhookSignature)?;
+
+    if bytes.len() != <Sha256 as hmac::digest::OutputSizeUser>::output_size() {
+        return Err(Error::MalformedWebhookSignature);
+    }
+
+    Ok(bytes)
+}
+
+/// Verify a webhook delivery with your AMT and the `SC-Signature` header,
+/// then deserialize its body.
+pub fn parse_webhook(body: &str, signature: &str, amt: &str) -> Result<WebhookPayload, Error> {
+    if !verify_payload(amt, signature, body)? {
+        return Err(Error::InvalidWebhookSignature);
+    }
+
+    Ok(serde_json::from_str::<WebhookPayload>(body)?)
 }
 
 #[test]
@@ -31,3 +95,44 @@ fn test_hash_challenge() {
 
     assert!(verified_payload);
 }
+
+#[test]
+fn test_hash_challenge_is_keyed_with_amt() {
+    let expecting = "2a596ed34b8c6f069492d952aa51f076fee7b1c662b6498e0217a0d41fd9b936";
+
+    assert_eq!(
+        hash_challenge("abc123abc123", "9c9c9c9c").unwrap(),
+        expecting
+    );
+}
+
+#[test]
+fn test_verify_payload_signature_formats() {
+    let body = "9c9c9c9c";
+    let signature = "2A596ED34B8C6F069492D952AA51F076FEE7B1C662B6498E0217A0D41FD9B936";
+
+    assert!(verify_payload("abc123abc123", signature, body).unwrap());
+    assert!(!verify_payload("wrong-amt", signature, body).unwrap());
+    assert!(verify_payload_with_amts(&["old-amt", "abc123abc123"], signature, body).unwrap());
+    assert!(matches!(
+        verify_payload("abc123abc123", "not-hex", body),
+        Err(Error::MalformedWebhookSignature)
+    ));
+    assert!(matches!(
+        verify_payload("abc123abc123", "abcd", body),
+        Err(Error::MalformedWebhookSignature)
+    ));
+}
+
+#[test]
+fn test_parse_webhook() {
+    let amt = "abc123abc123";
+    let body = r#"{"version":"2.0","webhookId":"webhook-1","eventName":"verify","payload":{"challenge":"xyz"}}"#;
+    let signature = hash_challenge(amt, body).unwrap();
+
+    assert!(parse_webhook(body, &signature, amt).is_ok());
+    assert!(matches!(
+        parse_webhook(body, &signature, "some-other-amt"),
+        Err(Error::InvalidWebhookSignature)
+    ));
+}
@@ -0,0 +1,47 @@
+//! A ready-made [axum](https://github.com/tokio-rs/axum) route for receiving webhooks
+//!
+//! Requires the `axum` feature.
+
+use std::sync::Arc;
+
+use ::axum::{
+    extract::Extension,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+
+use super::processor::WebhookProcessor;
+
+/// A router that receives webhook deliveries with POST requests to `/`.
+///
+/// Nest it at your webhook's callback path, e.g. `Router::new().nest("/webhooks", webhook_router(p))`
+pub fn webhook_router(processor: Arc<WebhookProcessor>) -> Router {
+    Router::new()
+        .route("/", post(handle_webhook))
+        .layer(Extension(processor))
+}
+
+/// The axum handler used by `webhook_router`.
+///
+/// Use this directly to mount the handler on your own route, along with an
+/// `Extension<Arc<WebhookProcessor>>` layer.
+pub async fn handle_webhook(
+    Extension(processor): Extension<Arc<WebhookProcessor>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let signature = headers
+        .get("SC-Signature")
+        .and_then(|value| value.to_str().ok());
+    let res = processor.process(&body, signature).await;
+    let status = StatusCode::from_u16(res.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        res.body,
+    )
+        .into_response()
+}
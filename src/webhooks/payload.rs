@@ -0,0 +1,314 @@
+//! These structs are representations of the webhook deliveries
+//! that Smartcar sends to your application
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SmartcarError;
+use crate::response::{
+    ApplicationPermissions, BatteryCapacity, BatteryLevel, ChargeLimit, ChargingStatus,
+    EngineOilLife, FuelTank, Location, LockStatus, Meta, Odometer, TirePressure, VehicleAttributes,
+    Vin,
+};
+
+/// A single webhook delivery
+///
+/// [More info on webhooks](https://smartcar.com/docs/integration-guides/webhooks/overview)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    pub version: String,
+    pub webhook_id: String,
+    pub mode: Option<String>,
+
+    #[serde(flatten)]
+    pub event: WebhookEvent,
+}
+
+impl WebhookPayload {
+    /// The id that Smartcar gives this delivery, if any: the `eventId` of an event-based delivery.
+    ///
+    /// Schedule and error deliveries have no id; `WebhookProcessor` recognizes their
+    /// redeliveries by a digest of the signature and body instead.
+    pub fn delivery_key(&self) -> Option<&str> {
+        match &self.event {
+            WebhookEvent::EventBased(e) => Some(&e.event_id),
+            _ => None,
+        }
+    }
+}
+
+/// The type of a webhook delivery (the `eventName`), along with its `payload`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "eventName", content = "payload")]
+pub enum WebhookEvent {
+    /// Sent when the webhook is first created, to verify your callback URI.
+    /// Respond with `webhooks::hash_challenge` of the challenge.
+    #[serde(rename = "verify")]
+    Verify(VerifyChallenge),
+
+    /// Vehicle data, sent on the schedule of the webhook
+    #[serde(rename = "schedule")]
+    Schedule(VehiclesDelivery),
+
+    /// Sent when an event (e.g. charging started) happens to a vehicle
+    #[serde(rename = "eventBased")]
+    EventBased(EventDelivery),
+
+    /// Sent when Smartcar could not get data from the vehicles of the webhook
+    #[serde(rename = "error")]
+    Error(VehiclesDelivery),
+}
+
+/// The payload of a VERIFY delivery
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VerifyChallenge {
+    pub challenge: String,
+}
+
+/// The payload of a schedule-based (or error) delivery
+#[derive(Debug, Deserialize, Serialize)]
+pub struct VehiclesDelivery {
+    pub vehicles: Vec<VehicleDelivery>,
+}
+
+/// The data of a single vehicle, keyed by endpoint path (e.g. `/odometer`)
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleDelivery {
+    pub vehicle_id: String,
+    pub request_id: Option<String>,
+
+    #[serde(with = "endpoint_data_list")]
+    pub data: HashMap<String, EndpointData>,
+}
+
+/// The payload of an event-based delivery
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventDelivery {
+    pub event_id: String,
+    pub vehicle_id: String,
+    pub event_type: String,
+    pub data: Value,
+}
+
+/// The response of a single endpoint for a vehicle
+#[derive(Debug)]
+pub struct EndpointData {
+    pub code: i32,
+    pub body: EndpointBody,
+    pub headers: Option<Meta>,
+}
+
+/// The body of a single endpoint's response, typed by its endpoint path
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum EndpointBody {
+    ApplicationPermissions(ApplicationPermissions),
+    BatteryCapacity(BatteryCapacity),
+    BatteryLevel(BatteryLevel),
+    ChargeLimit(ChargeLimit),
+    ChargingStatus(ChargingStatus),
+    EngineOilLife(EngineOilLife),
+    FuelTank(FuelTank),
+    Location(Location),
+    LockStatus(LockStatus),
+    Odometer(Odometer),
+    TirePressure(TirePressure),
+    VehicleAttributes(VehicleAttributes),
+    Vin(Vin),
+
+    /// The endpoint failed for this vehicle
+    Error(SmartcarError),
+
+    /// An endpoint without a typed response struct (e.g. a make-specific endpoint)
+    Other(Value),
+}
+
+impl EndpointBody {
+    /// Deserialize the body of an endpoint response, based on its path and status code
+    pub fn from_path(
+        path: &str,
+        code: i32,
+        body: Value,
+    ) -> Result<EndpointBody, serde_json::Error> {
+        if !(200..300).contains(&code) {
+            return Ok(EndpointBody::Error(serde_json::from_value(body)?));
+        }
+
+        let data = match path {
+            "/permissions" => EndpointBody::ApplicationPermissions(serde_json::from_value(body)?),
+            "/battery/capacity" => EndpointBody::BatteryCapacity(serde_json::from_value(body)?),
+            "/battery" => EndpointBody::BatteryLevel(serde_json::from_value(body)?),
+            "/charge/limit" => EndpointBody::ChargeLimit(serde_json::from_value(body)?),
+            "/charge" => EndpointBody::ChargingStatus(serde_json::from_value(body)?),
+            "/engine/oil" => EndpointBody::EngineOilLife(serde_json::from_value(body)?),
+            "/fuel" => EndpointBody::FuelTank(serde_json::from_value(body)?),
+            "/location" => EndpointBody::Location(serde_json::from_value(body)?),
+            "/security" => EndpointBody::LockStatus(serde_json::from_value(body)?),
+            "/odometer" => EndpointBody::Odometer(serde_json::from_value(body)?),
+            "/tires/pressure" => EndpointBody::TirePressure(serde_json::from_value(body)?),
+            "/" => EndpointBody::VehicleAttributes(serde_json::from_value(body)?),
+            "/vin" => EndpointBody::Vin(serde_json::from_value(body)?),
+            _ => EndpointBody::Other(body),
+        };
+
+        Ok(data)
+    }
+}
+
+/// (De)serializes the list of endpoint responses of a vehicle as a map keyed by path
+mod endpoint_data_list {
+    use std::collections::HashMap;
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    use super::{EndpointBody, EndpointData};
+    use crate::response::Meta;
+
+    #[derive(Deserialize)]
+    struct RawEndpointData {
+        path: String,
+        code: i32,
+        body: Value,
+        headers: Option<Meta>,
+    }
+
+    #[derive(Serialize)]
+    struct RawEndpointDataRef<'a> {
+        path: &'a str,
+        code: i32,
+        body: &'a EndpointBody,
+        headers: &'a Option<Meta>,
+    }
+
+    pub(super) fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<String, EndpointData>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = Vec::<RawEndpointData>::deserialize(deserializer)?;
+        let mut data = HashMap::new();
+
+        for r in raw {
+            let body =
+                EndpointBody::from_path(&r.path, r.code, r.body).map_err(de::Error::custom)?;
+            data.insert(
+                r.path,
+                EndpointData {
+                    code: r.code,
+                    body,
+                    headers: r.headers,
+                },
+            );
+        }
+
+        Ok(data)
+    }
+
+    pub(super) fn serialize<S>(
+        data: &HashMap<String, EndpointData>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut raw: Vec<RawEndpointDataRef> = data
+            .iter()
+            .map(|(path, d)| RawEndpointDataRef {
+                path,
+                code: d.code,
+                body: &d.body,
+                headers: &d.headers,
+            })
+            .collect();
+        raw.sort_by(|a, b| a.path.cmp(b.path));
+
+        raw.serialize(serializer)
+    }
+}
+
+#[test]
+fn test_deserialize_schedule_delivery() {
+    let body = r#"{
+        "version": "2.0",
+        "webhookId": "webhook-1",
+        "eventName": "schedule",
+        "mode": "test",
+        "payload": {
+            "vehicles": [
+                {
+                    "vehicleId": "vehicle-1",
+                    "requestId": "request-1",
+                    "data": [
+                        {
+                            "path": "/odometer",
+                            "code": 200,
+                            "body": { "distance": 1234.5 },
+                            "headers": { "sc-unit-system": "metric" }
+                        },
+                        {
+                            "path": "/location",
+                            "code": 409,
+                            "body": {
+                                "type": "VEHICLE_STATE",
+                                "code": "ASLEEP",
+                                "description": "The vehicle is asleep.",
+                                "docURL": "https://smartcar.com/docs/errors/v2.0/vehicle-state/#asleep",
+                                "statusCode": 409,
+                                "resolution": { "type": "RETRY_LATER" },
+                                "requestId": "request-1"
+                            }
+                        }
+                    ]
+                }
+            ]
+        }
+    }"#;
+    let payload: WebhookPayload = serde_json::from_str(body).unwrap();
+
+    let vehicles = match payload.event {
+        WebhookEvent::Schedule(delivery) => delivery.vehicles,
+        other => panic!("expected a schedule delivery, got {:?}", other),
+    };
+    let data = &vehicles[0].data;
+    assert!(matches!(
+        data["/odometer"].body,
+        EndpointBody::Odometer(Odometer { distance }) if distance == 1234.5
+    ));
+    assert!(matches!(data["/location"].body, EndpointBody::Error(_)));
+}
+
+#[test]
+fn test_deserialize_verify_and_event_deliveries() {
+    let verify = r#"{
+        "version": "2.0",
+        "webhookId": "webhook-1",
+        "eventName": "verify",
+        "payload": { "challenge": "abc123" }
+    }"#;
+    let payload: WebhookPayload = serde_json::from_str(verify).unwrap();
+    assert!(matches!(payload.event, WebhookEvent::Verify(v) if v.challenge == "abc123"));
+
+    let event = r#"{
+        "version": "2.0",
+        "webhookId": "webhook-1",
+        "eventName": "eventBased",
+        "mode": "live",
+        "payload": {
+            "eventId": "event-1",
+            "vehicleId": "vehicle-1",
+            "eventType": "CHARGING_STARTED",
+            "data": { "percentRemaining": 0.42 }
+        }
+    }"#;
+    let payload: WebhookPayload = serde_json::from_str(event).unwrap();
+    assert!(
+        matches!(payload.event, WebhookEvent::EventBased(e) if e.event_type == "CHARGING_STARTED")
+    );
+}
@@ -0,0 +1,469 @@
+//! A framework-agnostic webhook receiver
+//!
+//! Pass the raw body and `SC-Signature` header of each webhook delivery to
+//! `WebhookProcessor::process`, then respond with the returned status and body.
+
+use std::{collections::HashMap, fmt, future::Future, sync::Arc};
+
+use chrono::Duration;
+use futures::future::BoxFuture;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::payload::{WebhookEvent, WebhookPayload};
+use super::replay::SeenDeliveryStore;
+use super::{hash_challenge, verify_payload_with_amts};
+use crate::error::Error;
+use crate::secret::Secret;
+
+/// The error type returned by webhook handlers
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+type Handler =
+    Box<dyn Fn(WebhookDelivery) -> BoxFuture<'static, Result<(), HandlerError>> + Send + Sync>;
+
+/// A verified webhook delivery, as passed to a handler
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    /// The key used to recognize redeliveries and replays: the `eventId` of an event-based
+    /// delivery, otherwise `sha256:` and the hex SHA-256 digest of the signature and body
+    pub delivery_key: String,
+
+    /// True if a delivery with the same key was already received, and has been handled
+    /// successfully or is still being handled.
+    ///
+    /// This is only ever true if the processor has a `SeenDeliveryStore`.
+    pub duplicate: bool,
+
+    pub payload: WebhookPayload,
+}
+
+/// The types of webhook deliveries that can be handled
+///
+/// VERIFY deliveries are answered by the processor itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEventType {
+    Schedule,
+    EventBased,
+    Error,
+}
+
+impl WebhookEvent {
+    /// The type of this delivery, or `None` for a VERIFY delivery
+    pub fn event_type(&self) -> Option<WebhookEventType> {
+        match self {
+            WebhookEvent::Verify(_) => None,
+            WebhookEvent::Schedule(_) => Some(WebhookEventType::Schedule),
+            WebhookEvent::EventBased(_) => Some(WebhookEventType::EventBased),
+            WebhookEvent::Error(_) => Some(WebhookEventType::Error),
+        }
+    }
+}
+
+/// The HTTP response to send back to Smartcar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookResponse {
+    pub status: u16,
+
+    /// A JSON body
+    pub body: String,
+}
+
+impl WebhookResponse {
+    fn new(status: u16, body: serde_json::Value) -> WebhookResponse {
+        WebhookResponse {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    /// A response with a generic body for the status, since the caller may not be Smartcar.
+    /// The error itself is only logged.
+    fn error(status: u16, err: &dyn fmt::Display) -> WebhookResponse {
+        log_rejected_delivery(status, err);
+
+        let message = match status {
+            400 => "bad request",
+            401 => "invalid signature",
+            _ => "internal error",
+        };
+        WebhookResponse::new(status, json!({ "error": message }))
+    }
+}
+
+/// Logs why a delivery was not handled: a `tracing` event with the `tracing` feature, otherwise nothing
+#[cfg(feature = "tracing")]
+fn log_rejected_delivery(status: u16, err: &dyn fmt::Display) {
+    tracing::warn!(status, error = %err, "smartcar webhook delivery was not handled");
+}
+
+#[cfg(not(feature = "tracing"))]
+fn log_rejected_delivery(_status: u16, _err: &dyn fmt::Display) {}
+
+/// Verifies, parses, and dispatches webhook deliveries to your handlers.
+///
+/// - A `SC-Signature` header, if present, must be valid (401 if invalid, 400 if malformed),
+///   and is checked before the body is parsed
+/// - Bodies that cannot be parsed are rejected (400)
+/// - VERIFY deliveries are answered with `hash_challenge` of the challenge (200), without a
+///   signature unless `set_verify_requires_signature` is enabled
+/// - Every other delivery must have a signature (401 if missing)
+/// - Deliveries are passed to the handler registered for their type (500 if the handler fails),
+///   flagged as duplicates if they were already handled
+/// - Deliveries without a registered handler are acknowledged and ignored (200)
+///
+/// Error responses have a generic body. The error itself is logged as a `tracing` event
+/// when the `tracing` feature is enabled.
+pub struct WebhookProcessor {
+    amts: Vec<Secret>,
+    handlers: HashMap<WebhookEventType, Handler>,
+    max_age: Option<Duration>,
+    seen_store: Option<Arc<dyn SeenDeliveryStore>>,
+    verify_requires_signature: bool,
+}
+
+impl fmt::Debug for WebhookProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookProcessor")
+            .field("amts", &self.amts)
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .field("max_age", &self.max_age)
+            .field("seen_store", &self.seen_store.is_some())
+            .field("verify_requires_signature", &self.verify_requires_signature)
+            .finish()
+    }
+}
+
+impl WebhookProcessor {
+    pub fn new(amt: &str) -> WebhookProcessor {
+        WebhookProcessor {
+            amts: vec![Secret::from(amt)],
+            handlers: HashMap::new(),
+            max_age: None,
+            seen_store: None,
+            verify_requires_signature: false,
+        }
+    }
+
+    /// Also require a valid `SC-Signature` on VERIFY deliveries.
+    ///
+    /// Off by default: Smartcar's own examples answer the VERIFY challenge without checking
+    /// a signature, so it may not be signed. Only enable this once you have checked that
+    /// your webhook's VERIFY requests carry one, or registering the webhook will fail.
+    ///
+    /// Enable it if you can: the answer is the HMAC of the challenge keyed by your AMT, which is
+    /// also how deliveries are signed, so answering unsigned challenges lets anyone who can
+    /// reach your callback get a valid signature for a body of their choice.
+    pub fn set_verify_requires_signature(mut self, required: bool) -> Self {
+        self.verify_requires_signature = required;
+        self
+    }
+
+    /// The maximum age (or clock skew) of a delivery.
+    ///
+    /// This has no effect on current deliveries: Smartcar's v2 payloads do not include a
+    /// delivery timestamp to check. Replays of old signed deliveries are recognized by the
+    /// seen store instead (see `set_seen_store`), for as long as it remembers their keys.
+    pub fn set_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Recognize redeliveries and replays with a store of the delivery keys that were
+    /// already received (see `WebhookDelivery.delivery_key`).
+    ///
+    /// Each key is reserved before its handler runs, and released again if the handler fails,
+    /// so that Smartcar's retry is handled as a new delivery.
+    /// Redeliveries are still passed to the handler, with `WebhookDelivery.duplicate` set.
+    pub fn set_seen_store(mut self, store: Arc<dyn SeenDeliveryStore>) -> Self {
+        self.seen_store = Some(store);
+        self
+    }
+
+    /// Also accept deliveries signed with this AMT, e.g. while rotating your AMT.
+    ///
+    /// VERIFY challenges are always answered with the AMT passed to `WebhookProcessor::new`.
+    pub fn add_amt(mut self, amt: &str) -> Self {
+        self.amts.push(Secret::from(amt));
+        self
+    }
+
+    /// Register the async handler for a type of delivery, replacing any previous handler
+    pub fn on<F, Fut>(mut self, event_type: WebhookEventType, handler: F) -> Self
+    where
+        F: Fn(WebhookDelivery) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), HandlerError>> + Send + 'static,
+    {
+        self.handlers.insert(
+            event_type,
+            Box::new(move |delivery| Box::pin(handler(delivery))),
+        );
+        self
+    }
+
+    /// Process a single webhook delivery, given its raw body and `SC-Signature` header
+    pub async fn process(&self, body: &str, signature: Option<&str>) -> WebhookResponse {
+        if let Some(sig) = signature {
+            let amts: Vec<&str> = self.amts.iter().map(Secret::expose).collect();
+            match verify_payload_with_amts(&amts, sig, body) {
+                Ok(true) => {}
+                Ok(false) => return WebhookResponse::error(401, &Error::InvalidWebhookSignature),
+                Err(e) => return WebhookResponse::error(400, &e),
+            }
+        } else if self.verify_requires_signature {
+            return WebhookResponse::error(401, &Error::InvalidWebhookSignature);
+        }
+
+        let payload = match serde_json::from_str::<WebhookPayload>(body) {
+            Ok(p) => p,
+            Err(e) => return WebhookResponse::error(400, &Error::SdkSerdeFailure(e)),
+        };
+
+        // Only an unsigned VERIFY delivery gets this far without a signature
+        let event_type = match &payload.event {
+            WebhookEvent::Verify(verify) => {
+                return match hash_challenge(self.amts[0].expose(), &verify.challenge) {
+                    Ok(challenge) => WebhookResponse::new(200, json!({ "challenge": challenge })),
+                    Err(e) => WebhookResponse::error(500, &e),
+                };
+            }
+            other => other.event_type(),
+        };
+        let signature = match signature {
+            Some(sig) => sig,
+            None => return WebhookResponse::error(401, &Error::InvalidWebhookSignature),
+        };
+
+        let handler = match event_type.and_then(|t| self.handlers.get(&t)) {
+            Some(h) => h,
+            None => return WebhookResponse::new(200, json!({})),
+        };
+
+        let delivery_key = match payload.delivery_key() {
+            Some(id) => id.to_owned(),
+            None => delivery_digest(signature, body),
+        };
+        let reserved = self
+            .seen_store
+            .as_ref()
+            .map(|store| store.try_reserve(&delivery_key));
+        let duplicate = reserved == Some(false);
+        let delivery = WebhookDelivery {
+            delivery_key: delivery_key.to_owned(),
+            duplicate,
+            payload,
+        };
+
+        match handler(delivery).await {
+            Ok(()) => WebhookResponse::new(200, json!({})),
+            Err(e) => {
+                if let (Some(store), Some(true)) = (&self.seen_store, reserved) {
+                    store.release(&delivery_key);
+                }
+                WebhookResponse::error(500, &e)
+            }
+        }
+    }
+}
+
+/// The key of a delivery without an id: a digest of its (verified) signature and body
+fn delivery_digest(signature: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(signature.trim().to_ascii_lowercase().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body.as_bytes());
+    format!("sha256:{}", hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+const TEST_SCHEDULE_BODY: &str = r#"{"version":"2.0","webhookId":"webhook-1","eventName":"schedule","payload":{"vehicles":[{"vehicleId":"vehicle-1","data":[{"path":"/odometer","code":200,"body":{"distance":1.0}}]}]}}"#;
+
+#[tokio::test]
+async fn test_processor_answers_verify_challenge() {
+    let processor = WebhookProcessor::new("amt");
+    let body = r#"{"version":"2.0","webhookId":"webhook-1","eventName":"verify","payload":{"challenge":"xyz"}}"#;
+
+    let signature = hash_challenge("amt", body).unwrap();
+    let res = processor.process(body, Some(&signature)).await;
+    let expecting = json!({ "challenge": hash_challenge("amt", "xyz").unwrap() });
+    assert_eq!(res, WebhookResponse::new(200, expecting));
+
+    let unsigned = processor.process(body, None).await;
+    assert_eq!(unsigned, res);
+
+    let forged = hash_challenge("wrong-amt", body).unwrap();
+    assert_eq!(
+        processor
+            .process(body, Some(&forged))
+            .await
+            .status,
+        401
+    );
+
+    let strict = WebhookProcessor::new("amt").set_verify_requires_signature(true);
+    assert_eq!(strict.process(body, Some(&signature)).await, res);
+    let unsigned = strict.process(body, None).await;
+    assert_eq!(unsigned.status, 401);
+    assert!(!unsigned.body.contains("challenge"));
+    assert_eq!(strict.process("not json", None).await.status, 401);
+}
+
+#[tokio::test]
+async fn test_processor_checks_signature_and_dispatches() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler_calls = calls.clone();
+    let processor = WebhookProcessor::new("new-amt")
+        .add_amt("old-amt")
+        .on(WebhookEventType::Schedule, move |delivery| {
+            let calls = handler_calls.clone();
+            async move {
+                assert_eq!(delivery.payload.webhook_id, "webhook-1");
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+        .on(WebhookEventType::Error, |_| async {
+            Err::<(), HandlerError>("database is down".into())
+        });
+
+    let signature = hash_challenge("old-amt", TEST_SCHEDULE_BODY).unwrap();
+    let res = processor
+        .process(TEST_SCHEDULE_BODY, Some(&signature))
+        .await;
+    assert_eq!(res.status, 200);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let unsigned = processor.process(TEST_SCHEDULE_BODY, None).await;
+    assert_eq!(unsigned.status, 401);
+
+    let forged = hash_challenge("wrong-amt", TEST_SCHEDULE_BODY).unwrap();
+    let res = processor
+        .process(TEST_SCHEDULE_BODY, Some(&forged))
+        .await;
+    assert_eq!(res.status, 401);
+
+    let res = processor
+        .process(TEST_SCHEDULE_BODY, Some("zz"))
+        .await;
+    assert_eq!(res.status, 400);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let error_body = TEST_SCHEDULE_BODY.replace("\"schedule\"", "\"error\"");
+    let signature = hash_challenge("new-amt", &error_body).unwrap();
+    let res = processor
+        .process(&error_body, Some(&signature))
+        .await;
+    assert_eq!(
+        res,
+        WebhookResponse::new(500, json!({ "error": "internal error" }))
+    );
+}
+
+#[tokio::test]
+async fn test_processor_flags_duplicate_deliveries() {
+    use super::replay::InMemorySeenStore;
+    use std::sync::Mutex;
+
+    let keys = Arc::new(Mutex::new(Vec::new()));
+    let handler_keys = keys.clone();
+    let processor = WebhookProcessor::new("amt")
+        .set_seen_store(Arc::new(InMemorySeenStore::new(
+            std::time::Duration::from_secs(600),
+        )))
+        .on(WebhookEventType::EventBased, |_| async { Ok(()) })
+        .on(WebhookEventType::Schedule, move |delivery| {
+            handler_keys
+                .lock()
+                .unwrap()
+                .push((delivery.delivery_key, delivery.duplicate));
+            async { Ok(()) }
+        });
+
+    let event = r#"{"version":"2.0","webhookId":"webhook-1","eventName":"eventBased","payload":{"eventId":"event-1","vehicleId":"vehicle-1","eventType":"CHARGING_STARTED","data":{}}}"#;
+    let signature = hash_challenge("amt", event).unwrap();
+    assert_eq!(
+        processor
+            .process(event, Some(&signature))
+            .await
+            .status,
+        200
+    );
+
+    // Schedule deliveries have no id, so a replay is recognized by its signature and body
+    let signature = hash_challenge("amt", TEST_SCHEDULE_BODY).unwrap();
+    for _ in 0..2 {
+        let res = processor
+            .process(TEST_SCHEDULE_BODY, Some(&signature))
+            .await;
+        assert_eq!(res.status, 200);
+    }
+    let other_body = TEST_SCHEDULE_BODY.replace("1.0", "2.0");
+    let other_signature = hash_challenge("amt", &other_body).unwrap();
+    processor
+        .process(&other_body, Some(&other_signature))
+        .await;
+
+    let keys = keys.lock().unwrap();
+    assert!(keys[0].0.starts_with("sha256:"));
+    assert_eq!(keys[0].0, keys[1].0);
+    assert_ne!(keys[0].0, keys[2].0);
+    let duplicates: Vec<bool> = keys.iter().map(|(_, d)| *d).collect();
+    assert_eq!(duplicates, vec![false, true, false]);
+}
+
+#[tokio::test]
+async fn test_processor_reserves_concurrent_deliveries_once() {
+    use super::replay::InMemorySeenStore;
+    use std::sync::Mutex;
+
+    let duplicates = Arc::new(Mutex::new(Vec::new()));
+    let handler_duplicates = duplicates.clone();
+    let processor = WebhookProcessor::new("amt")
+        .set_seen_store(Arc::new(InMemorySeenStore::new(
+            std::time::Duration::from_secs(600),
+        )))
+        .on(WebhookEventType::EventBased, move |delivery| {
+            let duplicates = handler_duplicates.clone();
+            async move {
+                tokio::task::yield_now().await;
+                duplicates
+                    .lock()
+                    .unwrap()
+                    .push(delivery.duplicate);
+                if delivery.duplicate {
+                    Ok(())
+                } else {
+                    Err::<(), HandlerError>("database is down".into())
+                }
+            }
+        });
+
+    let body = r#"{"version":"2.0","webhookId":"webhook-1","eventName":"eventBased","payload":{"eventId":"event-1","vehicleId":"vehicle-1","eventType":"CHARGING_STARTED","data":{}}}"#;
+    let signature = hash_challenge("amt", body).unwrap();
+    let (first, second) = futures::join!(
+        processor.process(body, Some(&signature)),
+        processor.process(body, Some(&signature))
+    );
+
+    let mut statuses = vec![first.status, second.status];
+    statuses.sort_unstable();
+    assert_eq!(statuses, vec![200, 500]);
+    let mut seen = duplicates.lock().unwrap().clone();
+    seen.sort_unstable();
+    assert_eq!(seen, vec![false, true]);
+
+    // The failed delivery released its id, so Smartcar's retry is handled as new
+    assert_eq!(
+        processor
+            .process(body, Some(&signature))
+            .await
+            .status,
+        500
+    );
+    assert_eq!(duplicates.lock().unwrap().last(), Some(&false));
+}
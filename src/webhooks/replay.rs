@@ -0,0 +1,75 @@
+//! Stores of the webhook deliveries that have already been handled,
+//! used by `WebhookProcessor` to recognize redeliveries and replays.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Remembers the keys of received webhook deliveries (see `WebhookDelivery.delivery_key`)
+///
+/// Implement this over a shared store (e.g. Redis `SET NX`) when running more than one receiver.
+/// `try_reserve` must be atomic, so that two concurrent deliveries with the same key cannot
+/// both reserve it.
+pub trait SeenDeliveryStore: Send + Sync {
+    /// Remember the delivery key if it is not remembered yet.
+    ///
+    /// Returns true if this call reserved the key, false if it was already reserved.
+    fn try_reserve(&self, delivery_key: &str) -> bool;
+
+    /// Forget a reserved delivery key, e.g. because its handler failed and Smartcar will redeliver it
+    fn release(&self, delivery_key: &str);
+}
+
+/// An in-memory `SeenDeliveryStore` that forgets each delivery key after a time-to-live
+#[derive(Debug)]
+pub struct InMemorySeenStore {
+    ttl: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl InMemorySeenStore {
+    pub fn new(ttl: Duration) -> InMemorySeenStore {
+        InMemorySeenStore {
+            ttl,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl SeenDeliveryStore for InMemorySeenStore {
+    fn try_reserve(&self, delivery_key: &str) -> bool {
+        let mut seen = self
+            .seen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, seen_at| seen_at.elapsed() < self.ttl);
+
+        if seen.contains_key(delivery_key) {
+            return false;
+        }
+        seen.insert(delivery_key.to_owned(), Instant::now());
+        true
+    }
+
+    fn release(&self, delivery_key: &str) {
+        self.seen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(delivery_key);
+    }
+}
+
+#[test]
+fn test_in_memory_seen_store_expires_ids() {
+    let store = InMemorySeenStore::new(Duration::from_millis(20));
+    assert!(store.try_reserve("delivery-1"));
+    assert!(!store.try_reserve("delivery-1"));
+
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(store.try_reserve("delivery-1"));
+
+    store.release("delivery-1");
+    assert!(store.try_reserve("delivery-1"));
+}
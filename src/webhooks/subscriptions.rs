@@ -0,0 +1,180 @@
+//! Subscribe and unsubscribe many vehicles to a webhook at once.
+//!
+//! Subscribing uses each vehicle's access token, while unsubscribing uses your
+//! Application Management Token, through a `ManagementClient`.
+
+use std::collections::HashSet;
+
+use futures::{stream, StreamExt};
+
+use crate::error::Error;
+use crate::management::ManagementClient;
+use crate::response::Meta;
+use crate::vehicle::Vehicle;
+
+/// Whether a vehicle was subscribed to or unsubscribed from a webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionAction {
+    Subscribe,
+    Unsubscribe,
+}
+
+/// The result of subscribing or unsubscribing a single vehicle
+#[derive(Debug)]
+pub struct SubscriptionResult {
+    pub vehicle_id: String,
+    pub action: SubscriptionAction,
+    pub result: Result<Meta, Error>,
+}
+
+/// The results of subscribing and/or unsubscribing many vehicles
+#[derive(Debug, Default)]
+pub struct SubscriptionReport {
+    pub results: Vec<SubscriptionResult>,
+}
+
+impl SubscriptionReport {
+    /// The vehicles that were subscribed or unsubscribed
+    pub fn succeeded(&self) -> impl Iterator<Item = &SubscriptionResult> {
+        self.results.iter().filter(|r| r.result.is_ok())
+    }
+
+    /// The vehicles that could not be subscribed or unsubscribed
+    pub fn failed(&self) -> impl Iterator<Item = &SubscriptionResult> {
+        self.results.iter().filter(|r| r.result.is_err())
+    }
+}
+
+/// The vehicles to subscribe and unsubscribe so that exactly the desired
+/// vehicles are subscribed to a webhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionPlan {
+    pub to_subscribe: Vec<String>,
+    pub to_unsubscribe: Vec<String>,
+}
+
+impl SubscriptionPlan {
+    /// Compare the desired vehicle ids to those currently subscribed, keeping the order of each
+    pub fn new(desired: &[String], subscribed: &[String]) -> SubscriptionPlan {
+        let desired_set: HashSet<&String> = desired.iter().collect();
+        let subscribed_set: HashSet<&String> = subscribed.iter().collect();
+
+        SubscriptionPlan {
+            to_subscribe: unique(
+                desired
+                    .iter()
+                    .filter(|id| !subscribed_set.contains(id)),
+            ),
+            to_unsubscribe: unique(
+                subscribed
+                    .iter()
+                    .filter(|id| !desired_set.contains(id)),
+            ),
+        }
+    }
+
+    /// True if every desired vehicle is already subscribed, and no other
+    pub fn is_empty(&self) -> bool {
+        self.to_subscribe.is_empty() && self.to_unsubscribe.is_empty()
+    }
+}
+
+fn unique<'a>(ids: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ids.filter(|id| seen.insert(*id))
+        .cloned()
+        .collect()
+}
+
+/// Subscribe many vehicles to a webhook, sending at most `concurrency` requests at a time.
+///
+/// Every vehicle is attempted, even if some of them fail.
+pub async fn subscribe_all<'a>(
+    vehicles: impl IntoIterator<Item = &'a Vehicle>,
+    webhook_id: &str,
+    concurrency: usize,
+) -> SubscriptionReport {
+    let results = stream::iter(vehicles)
+        .map(|vehicle| async move {
+            let result = vehicle
+                .subscribe(webhook_id)
+                .await
+                .map(|(_, meta)| meta);
+            SubscriptionResult {
+                vehicle_id: vehicle.id.to_owned(),
+                action: SubscriptionAction::Subscribe,
+                result,
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    SubscriptionReport { results }
+}
+
+/// Unsubscribe many vehicles from a webhook, sending at most `concurrency` requests at a time.
+///
+/// Every vehicle is attempted, even if some of them fail.
+pub async fn unsubscribe_all(
+    client: &ManagementClient,
+    vehicle_ids: &[String],
+    webhook_id: &str,
+    concurrency: usize,
+) -> SubscriptionReport {
+    let results = stream::iter(vehicle_ids)
+        .map(|vehicle_id| async move {
+            let result = client
+                .unsubscribe(vehicle_id, webhook_id)
+                .await
+                .map(|(_, meta)| meta);
+            SubscriptionResult {
+                vehicle_id: vehicle_id.to_owned(),
+                action: SubscriptionAction::Unsubscribe,
+                result,
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    SubscriptionReport { results }
+}
+
+/// Subscribe the desired vehicles that are not subscribed yet, and unsubscribe
+/// the subscribed vehicles that are not desired.
+///
+/// `subscribed` holds the ids of the vehicles currently subscribed to the webhook,
+/// e.g. as tracked by your application from previous subscriptions.
+pub async fn reconcile_subscriptions(
+    client: &ManagementClient,
+    webhook_id: &str,
+    desired: &[Vehicle],
+    subscribed: &[String],
+    concurrency: usize,
+) -> SubscriptionReport {
+    let desired_ids: Vec<String> = desired.iter().map(|v| v.id.to_owned()).collect();
+    let plan = SubscriptionPlan::new(&desired_ids, subscribed);
+    let mut pending: HashSet<&String> = plan.to_subscribe.iter().collect();
+    let to_subscribe = desired.iter().filter(|v| pending.remove(&v.id));
+
+    let mut report = subscribe_all(to_subscribe, webhook_id, concurrency).await;
+    let unsubscribed = unsubscribe_all(client, &plan.to_unsubscribe, webhook_id, concurrency).await;
+    report.results.extend(unsubscribed.results);
+
+    report
+}
+
+#[test]
+fn test_subscription_plan() {
+    let ids = |ids: &[&str]| {
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+    };
+    let plan = SubscriptionPlan::new(&ids(&["a", "b", "c", "c"]), &ids(&["b", "d"]));
+
+    assert_eq!(plan.to_subscribe, ids(&["a", "c"]));
+    assert_eq!(plan.to_unsubscribe, ids(&["d"]));
+    assert!(SubscriptionPlan::new(&ids(&["a"]), &ids(&["a"])).is_empty());
+}
@@ -0,0 +1,243 @@
+//! Simulate Smartcar webhook deliveries, so your webhook receiver can be tested offline.
+//!
+//! `WebhookSimulator` builds realistic deliveries of every type, signs them with your AMT
+//! (just like Smartcar does), and POSTs them to your receiver.
+//!
+//! Requires the `testing` feature.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use super::hash_challenge;
+use super::payload::{
+    EndpointBody, EndpointData, EventDelivery, VehicleDelivery, VehiclesDelivery, VerifyChallenge,
+    WebhookEvent, WebhookPayload,
+};
+use crate::error::{Error, SmartcarError};
+use crate::response::{BatteryLevel, Location, Odometer};
+use crate::secret::Secret;
+
+static DELIVERY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The response of your receiver to a simulated delivery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Builds, signs, and sends webhook deliveries for a single webhook
+#[derive(Debug)]
+pub struct WebhookSimulator {
+    amt: Secret,
+    webhook_id: String,
+    mode: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSimulator {
+    /// Simulate the deliveries of a webhook, signed with this AMT
+    pub fn new(amt: &str, webhook_id: &str) -> WebhookSimulator {
+        WebhookSimulator {
+            amt: Secret::from(amt),
+            webhook_id: webhook_id.to_owned(),
+            mode: String::from("test"),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// A VERIFY delivery, as sent when a webhook is created
+    pub fn verify(&self, challenge: &str) -> WebhookPayload {
+        self.payload(WebhookEvent::Verify(VerifyChallenge {
+            challenge: challenge.to_owned(),
+        }))
+    }
+
+    /// A schedule-based delivery with the data of each vehicle
+    pub fn schedule(&self, vehicles: Vec<VehicleDelivery>) -> WebhookPayload {
+        self.payload(WebhookEvent::Schedule(VehiclesDelivery { vehicles }))
+    }
+
+    /// An event-based delivery for a single vehicle
+    pub fn event(&self, vehicle_id: &str, event_type: &str, data: Value) -> WebhookPayload {
+        self.payload(WebhookEvent::EventBased(EventDelivery {
+            event_id: next_id("event"),
+            vehicle_id: vehicle_id.to_owned(),
+            event_type: event_type.to_owned(),
+            data,
+        }))
+    }
+
+    /// An error delivery, where `path` failed with `error` for a single vehicle
+    pub fn error(&self, vehicle_id: &str, path: &str, error: SmartcarError) -> WebhookPayload {
+        let vehicle = vehicle_delivery(vehicle_id, vec![(path, EndpointBody::Error(error))]);
+        self.payload(WebhookEvent::Error(VehiclesDelivery {
+            vehicles: vec![vehicle],
+        }))
+    }
+
+    /// The `SC-Signature` that Smartcar would send with this body
+    pub fn sign(&self, body: &str) -> Result<String, Error> {
+        hash_challenge(self.amt.expose(), body)
+    }
+
+    /// Sign and POST a delivery to your receiver
+    pub async fn send(
+        &self,
+        url: &str,
+        payload: &WebhookPayload,
+    ) -> Result<SimulatedResponse, Error> {
+        let body = serde_json::to_string(payload)?;
+        let signature = self.sign(&body)?;
+
+        self.send_raw(url, &body, Some(&signature)).await
+    }
+
+    /// POST a delivery to your receiver with a signature from the wrong AMT
+    pub async fn send_with_bad_signature(
+        &self,
+        url: &str,
+        payload: &WebhookPayload,
+    ) -> Result<SimulatedResponse, Error> {
+        let body = serde_json::to_string(payload)?;
+        let signature = hash_challenge(&format!("not-{}", self.amt.expose()), &body)?;
+
+        self.send_raw(url, &body, Some(&signature)).await
+    }
+
+    /// POST any body to your receiver, with any (or no) `SC-Signature`
+    pub async fn send_raw(
+        &self,
+        url: &str,
+        body: &str,
+        signature: Option<&str>,
+    ) -> Result<SimulatedResponse, Error> {
+        let mut req = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.to_owned());
+        if let Some(sig) = signature {
+            req = req.header("SC-Signature", sig);
+        }
+
+        let res = req.send().await?;
+        let status = res.status().as_u16();
+        let body = res.text().await?;
+
+        Ok(SimulatedResponse { status, body })
+    }
+
+    fn payload(&self, event: WebhookEvent) -> WebhookPayload {
+        WebhookPayload {
+            version: String::from("2.0"),
+            webhook_id: self.webhook_id.to_owned(),
+            mode: Some(self.mode.to_owned()),
+            event,
+        }
+    }
+}
+
+/// The data of a single vehicle, with the response body of each endpoint path
+///
+/// The status code of each endpoint is 200, or the error's status code for `EndpointBody::Error`.
+pub fn vehicle_delivery(vehicle_id: &str, data: Vec<(&str, EndpointBody)>) -> VehicleDelivery {
+    let data: HashMap<String, EndpointData> = data
+        .into_iter()
+        .map(|(path, body)| {
+            let code = match &body {
+                EndpointBody::Error(e) => e.status_code,
+                _ => 200,
+            };
+            let endpoint_data = EndpointData {
+                code,
+                body,
+                headers: None,
+            };
+            (path.to_owned(), endpoint_data)
+        })
+        .collect();
+
+    VehicleDelivery {
+        vehicle_id: vehicle_id.to_owned(),
+        request_id: Some(next_id("request")),
+        data,
+    }
+}
+
+/// Realistic data of a single vehicle, for the odometer, location, and battery endpoints
+pub fn sample_vehicle_delivery(vehicle_id: &str) -> VehicleDelivery {
+    vehicle_delivery(
+        vehicle_id,
+        vec![
+            (
+                "/odometer",
+                EndpointBody::Odometer(Odometer { distance: 24387.2 }),
+            ),
+            (
+                "/location",
+                EndpointBody::Location(Location {
+                    latitude: 37.4292,
+                    longitude: -122.1381,
+                }),
+            ),
+            (
+                "/battery",
+                EndpointBody::BatteryLevel(BatteryLevel {
+                    percent_remaining: 0.42,
+                    range: 180.5,
+                }),
+            ),
+        ],
+    )
+}
+
+/// A realistic error, as returned when a vehicle is asleep
+pub fn sample_error() -> SmartcarError {
+    let error = json!({
+        "type": "VEHICLE_STATE",
+        "code": "ASLEEP",
+        "description": "The vehicle is in a sleep state and temporarily unable to perform your request.",
+        "docURL": "https://smartcar.com/docs/errors/v2.0/vehicle-state/#asleep",
+        "statusCode": 409,
+        "resolution": { "type": "RETRY_LATER" },
+        "requestId": next_id("request"),
+    });
+
+    serde_json::from_value(error).expect("a valid SmartcarError")
+}
+
+fn next_id(prefix: &str) -> String {
+    let count = DELIVERY_COUNT.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{}-{}", prefix, Utc::now().timestamp_millis(), count)
+}
+
+#[test]
+fn test_simulated_deliveries_parse_and_verify() {
+    use super::parse_webhook;
+
+    let simulator = WebhookSimulator::new("amt", "webhook-1");
+    let deliveries = [
+        simulator.verify("challenge"),
+        simulator.schedule(vec![sample_vehicle_delivery("vehicle-1")]),
+        simulator.event(
+            "vehicle-1",
+            "CHARGING_STARTED",
+            json!({ "percentRemaining": 0.5 }),
+        ),
+        simulator.error("vehicle-1", "/location", sample_error()),
+    ];
+
+    for delivery in deliveries {
+        let body = serde_json::to_string(&delivery).unwrap();
+        let signature = simulator.sign(&body).unwrap();
+        let parsed = parse_webhook(&body, &signature, "amt").unwrap();
+
+        assert_eq!(parsed.delivery_key(), delivery.delivery_key());
+    }
+}
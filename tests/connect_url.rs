@@ -0,0 +1,84 @@
+use proptest::prelude::*;
+use smartcar::{
+    auth_client::{AuthClient, AuthUrlOptionsBuilder, ConnectUrl},
+    compatibility::Country,
+    flags::Flags,
+    make::Make,
+    Permission, ScopeBuilder,
+};
+
+fn scope_strategy() -> impl Strategy<Value = ScopeBuilder> {
+    let permission = prop::sample::select(Permission::known().to_vec());
+    prop::collection::vec((permission, any::<bool>()), 0..8).prop_map(|permissions| {
+        permissions
+            .into_iter()
+            .fold(ScopeBuilder::new(), |scope, (p, required)| match required {
+                true => scope.add_required_permission(p),
+                false => scope.add_permission(p),
+            })
+    })
+}
+
+fn options_strategy() -> impl Strategy<Value = AuthUrlOptionsBuilder> {
+    (
+        prop::option::of(any::<bool>()),
+        prop::option::of(".*"),
+        prop::option::of(prop::sample::select(Make::known().to_vec())),
+        prop::option::of(any::<bool>()),
+        prop::option::of("[A-HJ-NPR-Z0-9]{17}"),
+        prop::option::of(prop::collection::vec(
+            ("[a-z_]{1,10}", "[a-z0-9_]{1,10}"),
+            0..4,
+        )),
+        prop::option::of("[a-z0-9-]{1,20}"),
+        prop::option::of(prop::sample::select(Country::all().to_vec())),
+    )
+        .prop_map(
+            |(force_prompt, state, make, single_select, vin, flags, user, country)| {
+                let mut options = AuthUrlOptionsBuilder::new();
+                options.force_prompt = force_prompt;
+                options.state = state;
+                options.make_bypass = make;
+                options.single_select = single_select;
+                options.single_select_by_vin = vin;
+                options.flags = flags.map(|flags| {
+                    flags
+                        .iter()
+                        .fold(Flags::new(), |f, (name, value)| f.add_flag(name, value))
+                });
+                options.user = user;
+                options.country = country;
+                options
+            },
+        )
+        .prop_filter("conflicting options", |options| options.validate().is_ok())
+}
+
+proptest! {
+    #[test]
+    fn connect_url_round_trips(
+        client_id in "[a-zA-Z0-9-]{1,36}",
+        redirect_uri in "https://[a-z]{1,10}\\.com/[a-z/]{0,10}",
+        test_mode in any::<bool>(),
+        scope in scope_strategy(),
+        options in options_strategy(),
+    ) {
+        let ac = AuthClient::new(&client_id, "client-secret", &redirect_uri, test_mode);
+        let url = ac.get_auth_url(&scope, Some(&options));
+
+        let parsed = ConnectUrl::parse(&url).unwrap();
+        prop_assert_eq!(&parsed.client_id, &client_id);
+        prop_assert_eq!(&parsed.redirect_uri, &redirect_uri);
+        prop_assert_eq!(parsed.test_mode, test_mode);
+        prop_assert_eq!(&parsed.scope.permissions, &scope.permissions);
+        prop_assert_eq!(&parsed.scope.required, &scope.required);
+        prop_assert_eq!(&parsed.options.state, &options.state);
+        prop_assert_eq!(&parsed.options.make_bypass, &options.make_bypass);
+        prop_assert_eq!(&parsed.options.user, &options.user);
+
+        let regenerated = parsed
+            .auth_client("client-secret")
+            .get_auth_url(&parsed.scope, Some(&parsed.options));
+        prop_assert_eq!(regenerated, url);
+    }
+}
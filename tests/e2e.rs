@@ -1,7 +1,9 @@
 use serial_test::serial;
 use smartcar::{
     auth_client::{AuthClient, AuthUrlOptionsBuilder},
+    compatibility::Country,
     get_user, get_vehicles,
+    make::Make,
     request::HttpVerb,
     vehicle::Vehicle,
     CompatibilityOptions, DeleteConnectionsFilters, Permission, ScopeBuilder,
@@ -15,13 +17,13 @@ mod helpers;
 #[serial]
 async fn full_e2e_bev() -> Result<(), Box<dyn std::error::Error>> {
     let (client_id, client_secret, redirect_uri, amt) = get_creds_from_env();
-    let scope = ScopeBuilder::with_all_permissions();
+    let scope = ScopeBuilder::with_all_permissions().add_permission(Permission::ReadCompass);
 
     // SET UP AUTH CLIENT
     let ac = AuthClient::new(&client_id, &client_secret, &redirect_uri, true);
     let get_auth_url_options = AuthUrlOptionsBuilder::new()
         .set_force_prompt(true)
-        .set_make_bypass(String::from("TESLA"));
+        .set_make_bypass(Make::Tesla);
 
     // GET ACCESS TOKEN
     let url = ac.get_auth_url(&scope, Some(&get_auth_url_options));
@@ -29,7 +31,7 @@ async fn full_e2e_bev() -> Result<(), Box<dyn std::error::Error>> {
 
     let code = run_connect_flow(&url, "4444").await?;
     let (access, _) = ac.exchange_code(&code).await?;
-    let access_token = &access.access_token;
+    let access_token = access.access_token.expose();
 
     // GET VEHICLES (and isolate one vehicle for testing)
     let (_, _) = get_user(&access).await?;
@@ -96,17 +98,15 @@ async fn full_e2e_bev() -> Result<(), Box<dyn std::error::Error>> {
     println!("batch: {:#?}", batch);
 
     // Compatibility
-    let compatibility_opts = CompatibilityOptions {
-        client_id: Some(client_id),
-        client_secret: Some(client_secret),
-        flags: None,
-    };
+    let compatibility_opts = CompatibilityOptions::new()
+        .set_client_id(&client_id)
+        .set_client_secret(&client_secret);
     let compatibility_scope =
         ScopeBuilder::new().add_permissions(vec![Permission::ReadBattery, Permission::ReadFuel]);
     let compatiblity = smartcar::get_compatibility(
         &vin.0.vin,
         &compatibility_scope,
-        "US",
+        Country::US,
         Some(compatibility_opts),
     )
     .await?;
@@ -136,7 +136,7 @@ async fn full_e2e_ice() -> Result<(), Box<dyn std::error::Error>> {
     let ac = AuthClient::new(&client_id, &client_secret, &redirect_uri, true);
     let get_auth_url_options = AuthUrlOptionsBuilder::new()
         .set_force_prompt(true)
-        .set_make_bypass(String::from("BUICK"));
+        .set_make_bypass(Make::Buick);
 
     // GET TOKENS
     let url = ac.get_auth_url(&scope, Some(&get_auth_url_options));
@@ -144,13 +144,13 @@ async fn full_e2e_ice() -> Result<(), Box<dyn std::error::Error>> {
     let (access, _) = ac.exchange_code(&code).await?;
 
     // TRY A REFRESH TOKEN EXCHANGE
-    let refresh_token = &access.refresh_token;
+    let refresh_token = access.refresh_token.expose();
     let (new_access, _) = ac.exchange_refresh_token(refresh_token).await?;
-    let access_token = new_access.access_token;
+    let access_token = new_access.access_token.expose();
 
     // GET VEHICLES & ISOLATE FIRST VEHICLE
     let (vehicles, _) = get_vehicles(&access, None, None).await?;
-    let v = Vehicle::new(&vehicles.vehicles[1], &access_token);
+    let v = Vehicle::new(&vehicles.vehicles[1], access_token);
     // println!("using first vehicle: {:#?}", v);
 
     let engine_oil = v.engine_oil().await?;
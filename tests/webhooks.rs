@@ -0,0 +1,93 @@
+use std::{
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use axum::Router;
+use serde_json::json;
+use smartcar::webhooks::{
+    axum::webhook_router,
+    hash_challenge,
+    processor::{WebhookEventType, WebhookProcessor},
+    testing::{sample_error, sample_vehicle_delivery, WebhookSimulator},
+};
+
+const AMT: &str = "test-amt";
+
+/// Serve the processor with the crate's axum route on a random local port, returning its url
+fn serve(processor: WebhookProcessor) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/webhooks", listener.local_addr().unwrap());
+    let app = Router::new().nest("/webhooks", webhook_router(Arc::new(processor)));
+
+    tokio::spawn(
+        axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service()),
+    );
+
+    url
+}
+
+#[tokio::test]
+async fn simulated_deliveries_reach_local_receiver() {
+    let handled = Arc::new(AtomicUsize::new(0));
+    let counter = handled.clone();
+    let processor = WebhookProcessor::new(AMT)
+        .on(WebhookEventType::Schedule, move |_| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+        .on(WebhookEventType::EventBased, |_| async { Ok(()) })
+        .on(WebhookEventType::Error, |_| async { Ok(()) });
+    let url = serve(processor);
+    let simulator = WebhookSimulator::new(AMT, "webhook-1");
+
+    let verify = simulator
+        .send(&url, &simulator.verify("challenge"))
+        .await
+        .unwrap();
+    let expected = json!({ "challenge": hash_challenge(AMT, "challenge").unwrap() });
+    assert_eq!(verify.status, 200);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&verify.body).unwrap(),
+        expected
+    );
+
+    let schedule = simulator.schedule(vec![sample_vehicle_delivery("vehicle-1")]);
+    assert_eq!(
+        simulator
+            .send(&url, &schedule)
+            .await
+            .unwrap()
+            .status,
+        200
+    );
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+    let event = simulator.event("vehicle-1", "CHARGING_STARTED", json!({}));
+    assert_eq!(simulator.send(&url, &event).await.unwrap().status, 200);
+
+    let error = simulator.error("vehicle-1", "/battery", sample_error());
+    assert_eq!(simulator.send(&url, &error).await.unwrap().status, 200);
+
+    let bad = simulator
+        .send_with_bad_signature(&url, &schedule)
+        .await
+        .unwrap();
+    assert_eq!(bad.status, 401);
+
+    let body = serde_json::to_string(&schedule).unwrap();
+    let unsigned = simulator
+        .send_raw(&url, &body, None)
+        .await
+        .unwrap();
+    assert_eq!(unsigned.status, 401);
+    assert_eq!(handled.load(Ordering::SeqCst), 1);
+}